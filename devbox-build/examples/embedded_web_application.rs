@@ -30,7 +30,7 @@ pub fn main() {
         webwrk_ndm.touch();
     });
 
-    webwrk_dst.mk_from("Build WebApp using webpack", &webwrk.content("**"), || {
+    webwrk_dst.mk_from("Build WebApp using webpack", webwrk.content("**"), || {
         npm.clone().arg("run").arg("build").run();
         webwrk_dst.touch();
     });
@@ -38,9 +38,9 @@ pub fn main() {
     //-- Package webapp into server binary as Rust source code -------------------------------------
 
     webrs.mk_from("Embed WebApp build into binary", &webwrk_dst, || {
-        let mappings = webwrk_dst.files("**").into_iter().map(|file|
+        let mappings = webwrk_dst.files("**").sorted().into_iter().map(|file|
             format!(r#""{}" => Some(include_bytes!("{}")),"#,
-                file.path().strip_prefix(&webwrk_dst.path()).unwrap().to_str().unwrap(),
+                file.path().strip_prefix(webwrk_dst.path()).unwrap().to_str().unwrap(),
                 file.path().to_str().unwrap())
         ).fold("".to_owned(), |result, ref s| result + s + "\n" );
 
@@ -1,8 +1,10 @@
-use std::env;
+use std::{collections::BTreeMap, env, path::Path, sync::OnceLock};
 
-pub use super::cmd::Cmd;
-pub use super::fs::{File, Dir, Unit};
-pub use super::res::{Resource, Set};
+use regex::Regex;
+
+use super::cmd::Cmd;
+use super::fs::{Dir, File};
+use super::res::Resource;
 
 //-- Build -----------------------------------------------------------------------------------------
 
@@ -21,10 +23,25 @@ pub struct Build {
 impl Build {
 
     /// Create new Build instance
+    ///
+    /// Accessors will stop the build if the Cargo environment variable they read is not set, use
+    /// [`try_new`](#method.try_new) to check for the core ones up front when not sure the code
+    /// runs as a build script.
     pub fn new() -> Self {
         Build {}
     }
 
+    /// Create new Build instance making sure the core Cargo environment variables (`OUT_DIR` and
+    /// `CARGO_MANIFEST_DIR`) are set, returning error naming the first missing one otherwise.
+    pub fn try_new() -> Result<Self, BuildError> {
+        for var in ["OUT_DIR", "CARGO_MANIFEST_DIR"] {
+            if env::var_os(var).is_none() {
+                return Err(BuildError::MissingEnvVar(var.to_owned()));
+            }
+        }
+        Ok(Build {})
+    }
+
     /// Current directory where the build has been run from
     //TODO: how os it different to manifest dir?
     pub fn current_dir(&self) -> Dir {
@@ -32,6 +49,12 @@ impl Build {
     }
 }
 
+impl Default for Build {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Accessors for envionment variables set by Cargo when running the script
 ///
 impl Build {
@@ -57,22 +80,110 @@ impl Build {
     /// Directory in which all output should be placed
     pub fn out_dir(&self) -> Dir { Dir::new(env::var("OUT_DIR").unwrap()) }
 
+    /// Stamp file with given `name` inside `.stamps` subdirectory of [`out_dir`](#method.out_dir)
+    ///
+    /// Stamps record that a build step without an output of it's own completed, by being touched
+    /// at it's end, and serve as the output of such a step:
+    ///
+    /// ```rust,no_run
+    /// # use devbox_build::{Build, Cmd, Resource};
+    /// let build = Build::new();
+    /// let stamp = build.stamp("npm-install");
+    /// stamp.mk_from("NPM install", &build.manifest_dir().file("package.json"), || {
+    ///     Cmd::new("npm").arg("install").run();
+    ///     stamp.touch();
+    /// });
+    /// ```
+    pub fn stamp(&self, name: &str) -> File { self.out_dir().dir(".stamps").file(name) }
+
+    /// Directory for scratch work of build steps, `.scratch` inside [`out_dir`](#method.out_dir)
+    ///
+    /// Meant for creating temporary directories in with [`Dir::temp`](struct.Dir.html#method.temp)
+    /// or [`Dir::temp_scoped`](struct.Dir.html#method.temp_scoped), keeping them out of the system
+    /// temp directory and next to the build output.
+    pub fn scratch_dir(&self) -> Dir { self.out_dir().dir(".scratch") }
+
+    /// Directory where Cargo places final artifacts of the current profile, like `target/debug`
+    /// or `target/<triple>/release` when cross compiling.
+    ///
+    /// There is no Cargo variable for it so it is derived from [`out_dir`](#method.out_dir), which
+    /// Cargo places at `<profile dir>/build/<package>-<hash>/out`, and will stop the build with
+    /// informative error message if the output directory is not laid out that way.
+    pub fn profile_dir(&self) -> Dir {
+        let out = env::var("OUT_DIR").unwrap();
+        let path = Path::new(&out);
+        let profile = match path.ancestors().nth(2) {
+            Some(build) if path.ends_with("out") && build.ends_with("build") => build.parent(),
+            _ => None,
+        };
+        Dir::new(profile.expect(format!("Profile dir of OUT_DIR {} NOT FOUND", out).as_str()))
+    }
+
+    /// Cargo target directory containing profile directories, like `target`, derived from
+    /// [`profile_dir`](#method.profile_dir) skipping the target triple directory when cross
+    /// compiling.
+    pub fn target_dir(&self) -> Dir {
+        let profile = self.profile_dir();
+        let mut target = profile.path().parent().unwrap();
+        if target.ends_with(self.target_triple()) {
+            target = target.parent().unwrap();
+        }
+        Dir::new(target)
+    }
+
+    /// Cargo profile the crate is being built with
+    pub fn profile(&self) -> Profile { Profile::from(env::var("PROFILE").unwrap().as_str()) }
+
     /// True if cargo profile is `release` (run with --release)
-    pub fn is_release_build(&self) -> bool { env::var("PROFILE").unwrap() == "release" }
+    pub fn is_release_build(&self) -> bool { self.profile() == Profile::Release }
 
     /// True if care is being build with `feature` enabled
     pub fn has_feature<P:AsRef<str>>(&self, feature: P) -> bool {
         Self::prefixed_env_var("CARGO_FEATURE_", feature).is_ok()
     }
 
+    /// Names of all enabled features, sorted, lowercase and with dashes instead of underscores
+    ///
+    /// Cargo does not distinguish the two in feature variable names, so feature `foo_bar` is
+    /// reported as `foo-bar` too.
+    pub fn features(&self) -> Vec<String> {
+        let mut features = env::vars()
+            .filter_map(|(name, _)| name.strip_prefix("CARGO_FEATURE_").map(|f| f.to_owned()))
+            .map(|feature| feature.to_lowercase().replace('_', "-"))
+            .collect::<Vec<_>>();
+        features.sort();
+        features
+    }
+
     /// Achitecure triple of the machine running the build
     pub fn host_triple(&self) -> String { env::var("HOST").unwrap() }
 
     /// Achitecure triple of the build binaries
     pub fn target_triple(&self) -> String { env::var("TARGET").unwrap() }
 
+    /// Architecture of the build binaries like `x86_64` or `aarch64`
+    pub fn target_arch(&self) -> String { self.cfg("target_arch").unwrap() }
+
+    /// Operating system of the build binaries like `linux`, `windows` or `macos`
+    pub fn target_os(&self) -> String { self.cfg("target_os").unwrap() }
+
+    /// Family of the build binaries like `unix` or `windows`, comma separated if there are more
+    pub fn target_family(&self) -> String { self.cfg("target_family").unwrap() }
+
+    /// Environment (ABI or libc) of the build binaries like `gnu`, `msvc` or empty if none
+    pub fn target_env(&self) -> String { self.cfg("target_env").unwrap() }
+
+    /// Pointer width of the build binaries in bits
+    pub fn target_pointer_width(&self) -> u16 {
+        self.cfg("target_pointer_width").unwrap().parse().unwrap()
+    }
+
+    /// True if the build binaries are for a different architecture triple than the one running the
+    /// build
+    pub fn is_cross_compiling(&self) -> bool { self.host_triple() != self.target_triple() }
+
     /// Number of threads to be used by the build
-    pub fn num_jobs(&self) -> u16 { u16::from_str_radix(&env::var("NUM_JOBS").unwrap(), 10).unwrap() }
+    pub fn num_jobs(&self) -> u16 { env::var("NUM_JOBS").unwrap().parse().unwrap() }
 
     /// Return configuration (check Cargo documentation above for more info)
     pub fn cfg<P:AsRef<str>>(&self, cfg: P) -> Option<String> {
@@ -81,6 +192,259 @@ impl Build {
 
     fn prefixed_env_var<P:AsRef<str>>(prefix: &str, name: P) -> Result<String, std::env::VarError> {
         let name = name.as_ref().to_owned().to_uppercase().replace("-", "_");
-        env::var(format!("{}_{}", prefix, name))
+        env::var(format!("{}{}", prefix, name))
+    }
+}
+
+/// Emitters of `cargo:` directives instructing Cargo how to build the crate
+///
+/// For more info on directives check [Cargo build script outputs]
+/// [Cargo build script outputs]:
+/// https://doc.rust-lang.org/cargo/reference/build-scripts.html#outputs-of-the-build-script
+///
+impl Build {
+
+    /// Enables custom configuration `cfg` like `foo` or `foo="bar"` when compiling the crate
+    pub fn rustc_cfg(&self, cfg: &str) {
+        println!("cargo:rustc-cfg={}", cfg);
+    }
+
+    /// Enables custom configuration `cfg` like [`rustc_cfg`](#method.rustc_cfg) and declares it as
+    /// expected using [`rustc_check_cfg`](#method.rustc_check_cfg) at the same time
+    pub fn rustc_cfg_declared(&self, cfg: &str) {
+        self.rustc_check_cfg(&Self::check_cfg_spec(cfg));
+        self.rustc_cfg(cfg);
+    }
+
+    /// Declares expected custom configuration `spec` like `cfg(foo)` or `cfg(foo, values("bar"))`
+    /// to avoid *unexpected cfg* warnings
+    pub fn rustc_check_cfg(&self, spec: &str) {
+        println!("cargo:rustc-check-cfg={}", spec);
+    }
+
+    /// Links the crate with native library `name` of optional `kind` like `static`, `dylib` or
+    /// `framework`
+    pub fn link_lib(&self, kind: Option<&str>, name: &str) {
+        match kind {
+            Some(kind) => println!("cargo:rustc-link-lib={}={}", kind, name),
+            None => println!("cargo:rustc-link-lib={}", name),
+        }
+    }
+
+    /// Adds directory `dir` to library search path for optional `kind` of libraries like `native`,
+    /// `dependency` or `framework`
+    pub fn link_search(&self, kind: Option<&str>, dir: &Dir) {
+        match kind {
+            Some(kind) => println!("cargo:rustc-link-search={}={}", kind, dir),
+            None => println!("cargo:rustc-link-search={}", dir),
+        }
+    }
+
+    /// Displays warning `msg` to the user after the build script finishes, one directive per line
+    /// of the message
+    pub fn warning(&self, msg: &str) {
+        for line in msg.lines() {
+            println!("cargo:warning={}", line);
+        }
+    }
+
+    /// Instructs Cargo to rerun the build script when file or directory at `path` changes
+    pub fn rerun_if_changed<P: AsRef<Path>>(&self, path: P) {
+        println!("cargo:rerun-if-changed={}", path.as_ref().display());
+    }
+
+    /// Instructs Cargo to rerun the build script when any of the `resource` paths changes, like
+    /// files in a [`Set`](struct.Set.html) or directory content, using
+    /// [`Resource::rerun_if_changed`](trait.Resource.html#method.rerun_if_changed)
+    pub fn rerun_if_resource_changed<R: Resource>(&self, resource: &R) {
+        resource.rerun_if_changed();
+    }
+
+    /// Instructs Cargo to rerun the build script when value of environment variable `var` changes
+    pub fn rerun_if_env_changed(&self, var: &str) {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
+
+    fn check_cfg_spec(cfg: &str) -> String {
+        match cfg.split_once('=') {
+            Some((name, value)) => format!("cfg({}, values({}))", name.trim(), value.trim()),
+            None => format!("cfg({})", cfg.trim()),
+        }
     }
-}
\ No newline at end of file
+}
+
+/// Code generation helpers
+///
+impl Build {
+
+    /// Writes `out` file declaring a module for every file in `src` directory matching `glob`
+    /// mirroring the directory structure with nested inline modules.
+    ///
+    /// Each file is declared as `#[path = "..."] pub mod name;` using it's absolute path, so `out`
+    /// can be placed anywhere (like in [`out_dir`](#method.out_dir)) and pulled in with
+    /// `include!`. Module names are derived from file and directory names by replacing characters
+    /// not allowed in identifiers with `_`. The file is only rewritten when its content changes.
+    pub fn generate_mod_tree<G: AsRef<str>>(&self, src: &Dir, out: &File, glob: G) {
+        let mut root = ModTree::default();
+        for path in src.files(glob).relative_paths(src) {
+            let mut node = &mut root;
+            if let Some(parent) = path.parent() {
+                for dir in parent.iter() {
+                    node = node.dirs.entry(Self::mod_ident(Path::new(dir))).or_default();
+                }
+            }
+            node.files.insert(Self::mod_ident(&path), src.path().join(&path).display().to_string());
+        }
+
+        let mut text = String::new();
+        root.write(&mut text, 0);
+        out.rewrite(text);
+    }
+
+    fn mod_ident(path: &Path) -> String {
+        let name = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+        let mut ident: String = name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+            .collect();
+
+        if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+            ident.insert(0, '_');
+        }
+        if KEYWORDS.contains(&ident.as_str()) {
+            ident.push('_');
+        }
+        ident
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe",
+    "use", "where", "while", "abstract", "become", "box", "do", "final", "macro", "override",
+    "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+#[derive(Default)]
+struct ModTree {
+    dirs: BTreeMap<String, ModTree>,
+    files: BTreeMap<String, String>,
+}
+
+impl ModTree {
+    fn write(&self, text: &mut String, depth: usize) {
+        let indent = "    ".repeat(depth);
+        for (name, path) in &self.files {
+            text.push_str(&format!("{}#[path = {:?}]\n{}pub mod {};\n", indent, path, indent, name));
+        }
+        for (name, tree) in &self.dirs {
+            text.push_str(&format!("{}pub mod {} {{\n", indent, name));
+            tree.write(text, depth + 1);
+            text.push_str(&format!("{}}}\n", indent));
+        }
+    }
+}
+
+/// Version checks of external tools used by the build
+///
+/// Tool versions are parsed from the output of running the tool command with `--version` argument
+/// by taking the first dotted number found (like `1.2` in `v1.2-beta`), or the first plain number
+/// when there is no dotted one. Numbers are then compared component by component.
+///
+impl Build {
+
+    /// Stops the build with informative error message if version of the tool run by `cmd` is lower
+    /// than `min` or if it can not be determined.
+    pub fn require_tool_version(&self, cmd: Cmd, min: &str) {
+        let program = cmd.command().get_program().to_owned();
+        match Self::tool_version(cmd) {
+            Some(version) if !Self::version_lower(&version, min) => (),
+            Some(version) => panic!("Tool {:?} version {} is lower than required {}",
+                program, version, min),
+            None => panic!("Tool {:?} version could not be determined", program),
+        }
+    }
+
+    /// Enables custom configuration `cfg` if version of the tool run by `cmd` is at or above `min`
+    /// returning true if it did, or false when the tool is missing or its version is unknown.
+    ///
+    /// Configuration is always declared as expected using
+    /// [`rustc_check_cfg`](#method.rustc_check_cfg) so it can be checked in code either way.
+    pub fn tool_version_cfg(&self, cmd: Cmd, min: &str, cfg: &str) -> bool {
+        self.rustc_check_cfg(&Self::check_cfg_spec(cfg));
+        match Self::tool_version(cmd) {
+            Some(version) if !Self::version_lower(&version, min) => {
+                self.rustc_cfg(cfg);
+                true
+            }
+            _ => false
+        }
+    }
+
+    fn tool_version(cmd: Cmd) -> Option<String> {
+        let output = cmd.arg("--version").output_result().ok()?;
+        Self::parse_version(&String::from_utf8_lossy(&output.stdout)).map(|v| v.to_owned())
+    }
+
+    fn parse_version(text: &str) -> Option<&str> {
+        static DOTTED: OnceLock<Regex> = OnceLock::new();
+        static NUMBER: OnceLock<Regex> = OnceLock::new();
+        DOTTED.get_or_init(|| Regex::new(r"\d+(\.\d+)+").unwrap()).find(text)
+            .or_else(|| NUMBER.get_or_init(|| Regex::new(r"\d+").unwrap()).find(text))
+            .map(|m| m.as_str())
+    }
+
+    fn version_lower(version: &str, min: &str) -> bool {
+        let parse = |v: &str| -> Vec<u64> { v.split('.').map(|n| n.parse().unwrap_or(0)).collect() };
+        let (mut version, mut min) = (parse(version), parse(min));
+        let len = version.len().max(min.len());
+        version.resize(len, 0);
+        min.resize(len, 0);
+        version < min
+    }
+}
+
+//-- BuildError ------------------------------------------------------------------------------------
+
+/// Error returned by [`Build::try_new`] when not running in a Cargo build script environment
+///
+/// [`Build::try_new`]: struct.Build.html#method.try_new
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// Required Cargo environment variable of given name is not set
+    MissingEnvVar(String),
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::MissingEnvVar(var) => {
+                write!(f, "Cargo environment variable {} is not set, not a build script?", var)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+//-- Profile ---------------------------------------------------------------------------------------
+
+/// Cargo profile the crate is being built with as returned by [`Build::profile`]
+///
+/// [`Build::profile`]: struct.Build.html#method.profile
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Profile {
+    Debug,
+    Release,
+    Custom(String),
+}
+
+impl From<&str> for Profile {
+    fn from(name: &str) -> Self {
+        match name {
+            "debug" => Profile::Debug,
+            "release" => Profile::Release,
+            _ => Profile::Custom(name.to_owned()),
+        }
+    }
+}
@@ -1,8 +1,15 @@
 use std::collections::HashMap;
 use std::ffi::{OsStr, OsString};
-use std::io::Result;
-use std::path::PathBuf;
-use std::process::{Command, ExitStatus, Output};
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use super::File;
+
+/// Number of last standard error lines included in failed command messages
+const STDERR_TAIL_LINES: usize = 20;
 
 //-- Cmd -------------------------------------------------------------------------------------------
 
@@ -24,7 +31,15 @@ pub struct Cmd {
     program: OsString,
     args: Vec<OsString>,
     envs: HashMap<OsString, OsString>,
+    removed: Vec<OsString>,
+    clear: bool,
     work: Option<PathBuf>,
+    stderr: Option<File>,
+    timeout: Option<Duration>,
+    stdin: Option<Vec<u8>>,
+    status: i32,
+    path: Vec<PathBuf>,
+    timed: bool,
 }
 
 impl Cmd {
@@ -35,10 +50,24 @@ impl Cmd {
             program: program.as_ref().to_owned(),
             args: vec![],
             envs: HashMap::new(),
+            removed: vec![],
+            clear: false,
             work: None,
+            stderr: None,
+            timeout: None,
+            stdin: None,
+            status: 0,
+            path: vec![],
+            timed: false,
         }
     }
 
+    /// Replaces the executable keeping all the arguments and environment variables
+    pub fn program<S: AsRef<OsStr>>(mut self, program: S) -> Self {
+        self.program = program.as_ref().to_owned();
+        self
+    }
+
     /// Adds an argument to the list of execution arguments
     pub fn arg<S: AsRef<OsStr>>(mut self, arg: S) -> Self {
         self.args.push(arg.as_ref().to_owned());
@@ -55,12 +84,118 @@ impl Cmd {
         self
     }
 
+    /// Adds an argument to the list of execution arguments only if `cond` is true
+    pub fn arg_if<S: AsRef<OsStr>>(self, cond: bool, arg: S) -> Self {
+        if cond { self.arg(arg) } else { self }
+    }
+
+    /// Adds multiple arguments to the list of execution arguments only if `cond` is true
+    pub fn args_if<I, S>(self, cond: bool, args: I) -> Self
+        where
+            I: IntoIterator<Item = S>,
+            S: AsRef<OsStr>,
+    {
+        if cond { self.args(args) } else { self }
+    }
+
     /// Sets an environment variable
     pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(mut self, env: K, val: V) -> Self {
+        self.removed.retain(|key| key != env.as_ref());
         self.envs.insert(env.as_ref().to_owned(), val.as_ref().to_owned());
         self
     }
 
+    /// Removes an environment variable inherited from the build process or set before
+    pub fn env_remove<K: AsRef<OsStr>>(mut self, env: K) -> Self {
+        self.envs.remove(env.as_ref());
+        self.removed.push(env.as_ref().to_owned());
+        self
+    }
+
+    /// Clears all environment variables inherited from the build process or set before
+    pub fn env_clear(mut self) -> Self {
+        self.envs.clear();
+        self.removed.clear();
+        self.clear = true;
+        self
+    }
+
+    /// Prepends `dir` to the `PATH` of the command, accepting [`Dir`](struct.Dir.html) as well
+    ///
+    /// `PATH` is built by [`command`](#method.command) from the one set by [`env`](#method.env) or
+    /// inherited from the build process (unless removed or cleared). Each call prepends in front of
+    /// the previous ones, so the directory added last is searched first.
+    pub fn path_prepend<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.path.insert(0, dir.as_ref().to_owned());
+        self
+    }
+
+    /// Sets the working directory of the command, accepting [`Dir`](struct.Dir.html) as well
+    pub fn current_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.work = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// Redirects standard error of the command into given `file` while standard output is inherited
+    /// from the build process.
+    ///
+    /// File is created (or truncated) when the command is built by [`command`](#method.command),
+    /// stopping the build with informative error message if that fails. Since both streams are
+    /// redirected, neither of them is captured by [`output`](#method.output).
+    pub fn stderr_to_file(mut self, file: &File) -> Self {
+        self.stderr = Some(file.clone());
+        self
+    }
+
+    /// Sets bytes written to standard input of the command when run by [`output`](#method.output),
+    /// [`stdout`](#method.stdout) or [`run`](#method.run) and their variants.
+    ///
+    /// Bytes are written from a separate thread so inputs larger than the pipe buffer do not
+    /// deadlock with the command writing it's output.
+    pub fn stdin_bytes(mut self, data: Vec<u8>) -> Self {
+        self.stdin = Some(data);
+        self
+    }
+
+    /// Connects standard output of this command to standard input of the `next` one creating a
+    /// [`Pipeline`](struct.Pipeline.html) without the need of a shell.
+    pub fn pipe_to(self, next: Cmd) -> Pipeline {
+        Pipeline { stages: vec![self, next] }
+    }
+
+    /// Limits how long [`run`](#method.run) and [`run_result`](#method.run_result) wait for the
+    /// command to complete, killing it when `timeout` expires.
+    ///
+    /// Expired commands are reported as errors of [`ErrorKind::TimedOut`] kind to distinguish them
+    /// from commands exiting with a nonzero status.
+    ///
+    /// [`ErrorKind::TimedOut`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.TimedOut
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the exit code the command is expected to exit with, instead of the default 0.
+    ///
+    /// Used by [`run`](#method.run), [`stdout`](#method.stdout),
+    /// [`output_result`](#method.output_result) and their variants to decide if execution succeeded
+    /// for tools using nonzero exit codes meaningfully, like `diff` returning 1 for differences.
+    /// Pipelines check each of their stages against it's expected status.
+    pub fn expect_status(mut self, code: i32) -> Self {
+        self.status = code;
+        self
+    }
+
+    /// Enables logging of wall-clock time each execution took, like
+    /// `Executed: "npm" ["run", "build"] (took 3.2s)`, to find build steps dominating build time.
+    ///
+    /// Applies to [`run`](#method.run), [`output`](#method.output), [`stdout`](#method.stdout) and
+    /// their variants logging the execution.
+    pub fn timed(mut self, yes: bool) -> Self {
+        self.timed = yes;
+        self
+    }
+
     /// Run the command and return it's output.
     ///
     /// This is convienece method for calling [`std::process::Command::output()`] method on command
@@ -70,25 +205,91 @@ impl Cmd {
     /// [`std::process::Command::output()`]:
     /// https://doc.rust-lang.org/std/process/struct.Command.html#method.output
     pub fn output(&self) -> Output {
-        println!("Executing: {:?} {:?} {:?}", self.program, self.args, self.envs);
-        self.command().output().expect(format!("Command executon '{:?} {:?} {:?}' failed",
+        log!("Executing: {}", self);
+        self.timing(|| self.capture()).expect(format!("Command executon '{:?} {:?} {:?}' failed",
             self.program, self.args, self.envs).as_str()
         )
     }
 
+    /// Run the command and return it's output using [`output_result`](#method.output_result) or
+    /// exit the build with informative panic message including exit status and captured standard
+    /// error if the command can not be executed or does not exit successfully.
+    pub fn checked_output(&self) -> Output {
+        self.output_result().unwrap_or_else(|err| panic!(
+            "Command executon '{:?} {:?} {:?}' failed: {}", self.program, self.args, self.envs, err
+        ))
+    }
+
+    /// Run the command and return it's output or a [`CmdError`](enum.CmdError.html) telling
+    /// whether the command could not be executed or exited with other than
+    /// [expected status](#method.expect_status), in which case it carries it's captured standard
+    /// error.
+    pub fn output_result(&self) -> std::result::Result<Output, CmdError> {
+        log!("Executing: {}", self);
+        let output = self.timing(|| self.capture()).map_err(|error| CmdError::Spawn {
+            program: self.program.clone(),
+            args: self.args.clone(),
+            error,
+        })?;
+
+        if self.expects(output.status) {
+            return Ok(output);
+        }
+
+        Err(CmdError::Exit {
+            program: self.program.clone(),
+            args: self.args.clone(),
+            status: output.status,
+            expected: self.status,
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+
     /// Run the command and exit the build with informative panic message if execution fails.
     ///
-    /// This is convienece method for calling [`std::process::Command::status()`] method on command
-    /// instance retrieved by [`command`] method
-    ///
-    /// [`command`]: #method.command
-    /// [`std::process::Command::status()`]:
-    /// https://doc.rust-lang.org/std/process/struct.Command.html#method.status
+    /// Standard error of the command is captured (unless redirected by
+    /// [`stderr_to_file`](#method.stderr_to_file)) and passed through after the command completes
+    /// so that the panic message can include exit status and the tail of it.
     pub fn run(&self) {
-        println!("Executing: {:?} {:?} {:?}", self.program, self.args, self.envs);
-        assert!(self.run_result().expect(format!("Command executon '{:?} {:?} {:?}' failed",
-            self.program, self.args, self.envs).as_str()
-        ).success());
+        log!("Executing: {}", self);
+        self.timing(|| self.run_captured()).unwrap_or_else(|err| panic!(
+            "Command executon '{:?} {:?} {:?}' failed: {}", self.program, self.args, self.envs, err
+        ))
+    }
+
+    fn run_captured(&self) -> Result<()> {
+        let mut command = self.command();
+        if self.stderr.is_none() {
+            command.stderr(Stdio::piped());
+        }
+
+        let mut child = self.spawn_command(command)?;
+        let reader = read_pipe(child.stderr.take());
+
+        let status = self.wait(&mut child)?;
+        let stderr = join_pipe(reader)?;
+
+        std::io::stderr().write_all(&stderr)?;
+        self.successful(Output { status, stdout: vec![], stderr }).map(|_| ())
+    }
+
+    /// Start the command without waiting for it to complete using [`spawn`](#method.spawn) or exit
+    /// the build with informative panic message if it can not be executed.
+    pub fn spawn_checked(&self) -> Child {
+        self.spawn().unwrap_or_else(|err| panic!(
+            "Command executon '{:?} {:?} {:?}' failed: {}", self.program, self.args, self.envs, err
+        ))
+    }
+
+    /// Start the command without waiting for it to complete, returning the child process handle,
+    /// so independent commands can run concurrently and be waited for together.
+    ///
+    /// Bytes set by [`stdin_bytes`](#method.stdin_bytes) are written to the child, but waiting for
+    /// it, enforcing [`timeout`](#method.timeout) and checking it's exit status are left to the
+    /// caller.
+    pub fn spawn(&self) -> Result<Child> {
+        log!("Executing: {}", self);
+        self.spawn_command(self.command())
     }
 
     /// Run the command and return it's status.
@@ -99,8 +300,159 @@ impl Cmd {
     /// [`command`]: #method.command
     /// [`std::process::Command::status()`]:
     /// https://doc.rust-lang.org/std/process/struct.Command.html#method.status
+    ///
+    /// If [`timeout`](#method.timeout) is set, the command is killed when it expires and an error
+    /// is returned instead.
     pub fn run_result(&self) -> Result<ExitStatus> {
-        self.command().status()
+        match (self.timeout, &self.stdin) {
+            (None, None) => self.command().status(),
+            _ => self.wait(&mut self.spawn_command(self.command())?),
+        }
+    }
+
+    fn timing<T>(&self, execute: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = execute();
+        if self.timed {
+            log!("Executed: {} (took {:.1?})", self, start.elapsed());
+        }
+        result
+    }
+
+    fn capture(&self) -> Result<Output> {
+        if self.stdin.is_none() {
+            return self.command().output();
+        }
+
+        let mut command = self.command();
+        command.stdout(Stdio::piped());
+        if self.stderr.is_none() {
+            command.stderr(Stdio::piped());
+        }
+        self.spawn_command(command)?.wait_with_output()
+    }
+
+    fn spawn_command(&self, mut command: Command) -> Result<Child> {
+        let data = match &self.stdin {
+            Some(data) => data.clone(),
+            None => return command.spawn(),
+        };
+
+        let mut child = command.stdin(Stdio::piped()).spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            std::thread::spawn(move || stdin.write_all(&data));
+        }
+        Ok(child)
+    }
+
+    fn wait(&self, child: &mut Child) -> Result<ExitStatus> {
+        let timeout = match self.timeout {
+            Some(timeout) => timeout,
+            None => return child.wait(),
+        };
+
+        let start = Instant::now();
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(status);
+            }
+
+            if start.elapsed() >= timeout {
+                child.kill()?;
+                child.wait()?;
+                let message = format!("timed out after {:?}", timeout);
+                return Err(Error::new(ErrorKind::TimedOut, message));
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Run the command returning it's trimmed standard output using
+    /// [`stdout_result`](#method.stdout_result) or exit the build with informative panic message
+    /// including captured standard error if execution fails.
+    pub fn stdout(&self) -> String {
+        self.stdout_result().unwrap_or_else(|err| panic!(
+            "Command executon '{:?} {:?} {:?}' failed: {}", self.program, self.args, self.envs, err
+        ))
+    }
+
+    /// Run the command returning it's standard output as trimmed string or an error containing
+    /// exit status and captured standard error if the command does not exit successfully.
+    pub fn stdout_result(&self) -> Result<String> {
+        log!("Executing: {}", self);
+        let output = self.successful(self.timing(|| self.capture())?)?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    }
+
+    /// Run the command using [`run`](#method.run) returning wall-clock time it took to complete.
+    pub fn timed_run(&self) -> Duration {
+        let start = Instant::now();
+        self.run();
+        start.elapsed()
+    }
+
+    /// Run the command using [`output`](#method.output) returning it's output together with
+    /// wall-clock time it took to complete.
+    pub fn timed_output(&self) -> (Output, Duration) {
+        let start = Instant::now();
+        let output = self.output();
+        (output, start.elapsed())
+    }
+
+    /// `PATH` set for the command, with prepended directories if any, or None if not changed
+    fn path_var(&self) -> Option<OsString> {
+        let path = self.envs.get(OsStr::new("PATH")).cloned();
+        if self.path.is_empty() {
+            return path;
+        }
+
+        let inherited = !self.clear && !self.removed.iter().any(|env| env == "PATH");
+        let path = path.or_else(|| std::env::var_os("PATH").filter(|_| inherited));
+        let tail = path.iter().flat_map(std::env::split_paths);
+        let paths: Vec<PathBuf> = self.path.iter().cloned().chain(tail).collect();
+        let joined = std::env::join_paths(paths);
+        Some(joined.expect(format!("Joining PATH {:?} FAILED", self.path).as_str()))
+    }
+
+    fn expects(&self, status: ExitStatus) -> bool {
+        status.code() == Some(self.status)
+    }
+
+    fn successful(&self, output: Output) -> Result<Output> {
+        if self.expects(output.status) {
+            return Ok(output);
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(Error::other(failure_message(output.status, self.status, &stderr)))
+    }
+
+    /// Path of the executable found by searching `PATH` directories, `None` if it is not found
+    ///
+    /// `PATH` set on this Cmd takes precedence over the one of the build process. Programs given
+    /// with a directory part are not searched for but checked relative to the
+    /// [`current_dir`](#method.current_dir). On Windows names without one of the `PATHEXT`
+    /// extensions are tried with each of them appended, in `PATHEXT` order.
+    pub fn resolve(&self) -> Option<PathBuf> {
+        let program = Path::new(&self.program);
+        if program.is_absolute() || program.components().count() > 1 {
+            let path = match &self.work {
+                Some(work) => work.join(program),
+                None => program.to_owned(),
+            };
+            return executable_candidates(&path).into_iter().find(|path| is_executable(path));
+        }
+
+        let paths = self.path_var().or_else(|| std::env::var_os("PATH"))?;
+        std::env::split_paths(&paths)
+            .flat_map(|dir| executable_candidates(&dir.join(program)))
+            .find(|path| is_executable(path))
+    }
+
+    /// Check if the program can be found using [`resolve`](#method.resolve)
+    pub fn exists(&self) -> bool {
+        self.resolve().is_some()
     }
 
     /// Build the `std::process::Command` with args and environment variables set up by methods on
@@ -108,12 +460,368 @@ impl Cmd {
     pub fn command(&self) -> Command {
         let mut command = Command::new(&self.program);
         command.args(&self.args);
+
+        if self.clear {
+            command.env_clear();
+        }
+        for env in &self.removed {
+            command.env_remove(env);
+        }
         command.envs(&self.envs);
+        if !self.path.is_empty() {
+            command.env("PATH", self.path_var().unwrap_or_default());
+        }
 
         if let Some(work_dir) = &self.work {
             command.current_dir(work_dir);
         }
 
+        if let Some(file) = &self.stderr {
+            command.stdout(Stdio::inherit());
+            command.stderr(file.create());
+        }
+
         command
     }
-}
\ No newline at end of file
+}
+
+/// Shell-like single line with environment variables set by [`env`](#method.env) or
+/// [`path_prepend`](#method.path_prepend), program and arguments, quoted when needed, so it can
+/// be copied from the build output and ran in a terminal
+impl std::fmt::Display for Cmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut envs: Vec<(&OsStr, OsString)> = self.envs.iter()
+            .filter(|(key, _)| self.path.is_empty() || key.as_os_str() != "PATH")
+            .map(|(key, val)| (key.as_os_str(), val.clone()))
+            .collect();
+        if let Some(path) = self.path_var().filter(|_| !self.path.is_empty()) {
+            envs.push((OsStr::new("PATH"), path));
+        }
+        envs.sort();
+
+        for (key, val) in envs {
+            write!(f, "{}={} ", key.to_string_lossy(), shell_quote(&val))?;
+        }
+        write!(f, "{}", shell_quote(&self.program))?;
+        for arg in &self.args {
+            write!(f, " {}", shell_quote(arg))?;
+        }
+        Ok(())
+    }
+}
+
+/// Argument quoted for a shell if it is empty or contains anything but safe characters
+fn shell_quote(arg: &OsStr) -> String {
+    let arg = arg.to_string_lossy();
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        return arg.into_owned();
+    }
+
+    if cfg!(windows) {
+        format!("\"{}\"", arg.replace('"', "\\\""))
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Paths to check for an executable `path`, trying `PATHEXT` extensions on Windows
+#[cfg(windows)]
+fn executable_candidates(path: &Path) -> Vec<PathBuf> {
+    let pathext = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_owned());
+    let exts: Vec<&str> = pathext.split(';').filter(|ext| !ext.is_empty()).collect();
+
+    let has_ext = path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| {
+        exts.iter().any(|known| known.trim_start_matches('.').eq_ignore_ascii_case(ext))
+    });
+    if has_ext {
+        return vec![path.to_owned()];
+    }
+
+    exts.iter()
+        .map(|ext| {
+            let mut name = path.as_os_str().to_owned();
+            name.push(ext);
+            PathBuf::from(name)
+        })
+        .collect()
+}
+
+/// Paths to check for an executable `path`, trying `PATHEXT` extensions on Windows
+#[cfg(not(windows))]
+fn executable_candidates(path: &Path) -> Vec<PathBuf> {
+    vec![path.to_owned()]
+}
+
+/// Check if `path` is a file with any of the execute permission bits set
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+/// Check if `path` is a file, executability being decided by it's extension
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Message describing unexpected exit `status` including the tail of captured standard error
+fn failure_message(status: ExitStatus, expected: i32, stderr: &str) -> String {
+    if expected == 0 {
+        format!("{}, stderr: {}", status, stderr_tail(stderr))
+    } else {
+        format!("{} (expected {}), stderr: {}", status, expected, stderr_tail(stderr))
+    }
+}
+
+/// Last [`STDERR_TAIL_LINES`] lines of trimmed standard error prefixed with `...` if there are more
+fn stderr_tail(stderr: &str) -> String {
+    let lines: Vec<&str> = stderr.trim().lines().collect();
+    if lines.len() > STDERR_TAIL_LINES {
+        format!("...\n{}", lines[lines.len() - STDERR_TAIL_LINES..].join("\n"))
+    } else {
+        lines.join("\n")
+    }
+}
+
+//-- CmdError --------------------------------------------------------------------------------------
+
+/// Error returned by [`Cmd::output_result`](struct.Cmd.html#method.output_result)
+///
+/// Distinguishes commands that could not be executed at all from those that ran but did not exit
+/// successfully, carrying their captured standard error for further inspection.
+///
+#[derive(Debug)]
+pub enum CmdError {
+    /// Command could not be executed, like when the program does not exist
+    Spawn { program: OsString, args: Vec<OsString>, error: Error },
+    /// Command ran but exited with other than expected status or was terminated by a signal
+    Exit {
+        program: OsString,
+        args: Vec<OsString>,
+        status: ExitStatus,
+        expected: i32,
+        stderr: String,
+    },
+}
+
+impl CmdError {
+
+    /// Exit code of the command or None if it could not be executed or was terminated by a signal
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            CmdError::Spawn { .. } => None,
+            CmdError::Exit { status, .. } => status.code(),
+        }
+    }
+
+    /// Captured standard error of the command, empty if it could not be executed
+    pub fn stderr(&self) -> &str {
+        match self {
+            CmdError::Spawn { .. } => "",
+            CmdError::Exit { stderr, .. } => stderr,
+        }
+    }
+}
+
+impl std::fmt::Display for CmdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CmdError::Spawn { error, .. } => error.fmt(f),
+            CmdError::Exit { status, expected, stderr, .. } => {
+                f.write_str(&failure_message(*status, *expected, stderr))
+            }
+        }
+    }
+}
+
+impl std::error::Error for CmdError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CmdError::Spawn { error, .. } => Some(error),
+            CmdError::Exit { .. } => None,
+        }
+    }
+}
+
+//-- Pipeline --------------------------------------------------------------------------------------
+
+/// Chain of commands with standard output of each connected to standard input of the next one
+///
+/// Created by [`Cmd::pipe_to`](struct.Cmd.html#method.pipe_to) and run without a shell which keeps
+/// build scripts portable between platforms with different shell piping syntax. Errors name the
+/// failing stage by it's position (starting with 1) and program. Stage
+/// [`timeout`](struct.Cmd.html#method.timeout) counts from the start of the pipeline and kills all
+/// of the stages when it expires.
+///
+#[derive(Clone, Debug)]
+pub struct Pipeline {
+    stages: Vec<Cmd>,
+}
+
+impl Pipeline {
+
+    /// Appends `next` command to the end of the pipeline
+    pub fn pipe_to(mut self, next: Cmd) -> Self {
+        self.stages.push(next);
+        self
+    }
+
+    /// Run the pipeline and return output of the last stage or exit the build with informative
+    /// panic message if any stage can not be executed or if any but the last stage fails.
+    pub fn output(&self) -> Output {
+        self.output_result().unwrap_or_else(|err| panic!("Pipeline executon failed: {}", err))
+    }
+
+    /// Run the pipeline and return output of the last stage or an error naming the stage that
+    /// could not be executed or that failed, if it is not the last one. Failures of earlier stages
+    /// are only reported if the last stage succeeds, starting with the stage closest to it.
+    pub fn output_result(&self) -> Result<Output> {
+        self.execute(true)
+    }
+
+    /// Run the pipeline and exit the build with informative panic message naming the failed stage
+    /// if any of them fails. Output of the last stage is inherited from the build process.
+    pub fn run(&self) {
+        let last = &self.stages[self.stages.len() - 1];
+        let result = self.run_result().and_then(|status| if last.expects(status) {
+            Ok(())
+        } else {
+            Err(self.stage_error(self.stages.len() - 1, status.to_string()))
+        });
+        result.unwrap_or_else(|err| panic!("Pipeline executon failed: {}", err))
+    }
+
+    /// Run the pipeline and return status of the last stage or an error naming the stage that
+    /// could not be executed or that failed, if it is not the last one.
+    pub fn run_result(&self) -> Result<ExitStatus> {
+        self.execute(false).map(|output| output.status)
+    }
+
+    fn execute(&self, capture: bool) -> Result<Output> {
+        let last = self.stages.len() - 1;
+        let mut children = vec![];
+        let mut input = None;
+
+        for (index, stage) in self.stages.iter().enumerate() {
+            log!("Executing: {}", stage);
+            let mut command = stage.command();
+            if let Some(stdout) = input.take() {
+                command.stdin(Stdio::from(stdout));
+            }
+            if index < last || capture {
+                command.stdout(Stdio::piped());
+            }
+            if index == last && capture && stage.stderr.is_none() {
+                command.stderr(Stdio::piped());
+            }
+
+            let spawned = match index {
+                0 => stage.spawn_command(command),
+                _ => command.spawn(),
+            };
+            let mut child = match spawned {
+                Ok(child) => child,
+                Err(err) => {
+                    kill_all(&mut children);
+                    return Err(self.stage_error(index, err.to_string()));
+                }
+            };
+            if index < last {
+                input = child.stdout.take();
+            }
+            children.push(child);
+        }
+
+        let stdout = read_pipe(children[last].stdout.take());
+        let stderr = read_pipe(children[last].stderr.take());
+        let statuses = self.wait_all(&mut children);
+        let stdout = join_pipe(stdout).map_err(|err| self.stage_error(last, err.to_string()))?;
+        let stderr = join_pipe(stderr).map_err(|err| self.stage_error(last, err.to_string()))?;
+        let mut statuses = statuses?;
+        let output = Output { status: statuses.pop().unwrap(), stdout, stderr };
+
+        // Earlier stages are reported only when the last one succeeds as their failure is often
+        // just a consequence of the later stage exiting early (like broken pipe)
+        let failed = statuses.iter().enumerate().rev()
+            .find(|(index, status)| !self.stages[*index].expects(**status))
+            .map(|(index, status)| self.stage_error(index, status.to_string()));
+
+        match failed {
+            Some(err) if self.stages[last].expects(output.status) => Err(err),
+            _ => Ok(output),
+        }
+    }
+
+    /// Wait for all stages starting with the last one, killing all of them if any stage times out
+    /// or can not be waited for
+    fn wait_all(&self, children: &mut [Child]) -> Result<Vec<ExitStatus>> {
+        let start = Instant::now();
+        let mut statuses = vec![None; children.len()];
+        let timed = self.stages.iter().any(|stage| stage.timeout.is_some());
+
+        loop {
+            for index in (0..children.len()).rev() {
+                if statuses[index].is_some() {
+                    continue;
+                }
+
+                let waited = if timed {
+                    children[index].try_wait()
+                } else {
+                    children[index].wait().map(Some)
+                };
+                let message = match (waited, self.stages[index].timeout) {
+                    (Ok(Some(status)), _) => {
+                        statuses[index] = Some(status);
+                        continue;
+                    }
+                    (Ok(None), Some(timeout)) if start.elapsed() >= timeout =>
+                        format!("timed out after {:?}", timeout),
+                    (Ok(None), _) => continue,
+                    (Err(err), _) => err.to_string(),
+                };
+
+                kill_all(children);
+                return Err(self.stage_error(index, message));
+            }
+
+            if statuses.iter().all(Option::is_some) {
+                return Ok(statuses.into_iter().map(Option::unwrap).collect());
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn stage_error(&self, index: usize, message: String) -> Error {
+        let stage = &self.stages[index];
+        let stage = format!("stage {} '{:?} {:?}'", index + 1, stage.program, stage.args);
+        Error::other(format!("{}: {}", stage, message))
+    }
+}
+
+/// Kill and reap all `children`, ignoring errors as some of them may have already exited
+fn kill_all(children: &mut [Child]) {
+    for child in children {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// Read the whole child process `pipe` on a separate thread so it can't block waiting for the child
+fn read_pipe<R: Read + Send + 'static>(pipe: Option<R>) -> Option<JoinHandle<Result<Vec<u8>>>> {
+    pipe.map(|mut pipe| std::thread::spawn(move || {
+        let mut bytes = vec![];
+        pipe.read_to_end(&mut bytes).map(|_| bytes)
+    }))
+}
+
+/// Bytes read by [`read_pipe`] or empty if there was no pipe to read
+fn join_pipe(reader: Option<JoinHandle<Result<Vec<u8>>>>) -> Result<Vec<u8>> {
+    match reader {
+        Some(reader) => reader.join().unwrap_or_else(|_| Ok(vec![])),
+        None => Ok(vec![]),
+    }
+}
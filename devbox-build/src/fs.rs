@@ -1,14 +1,19 @@
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use std::ffi::OsStr;
 use std::marker::PhantomData;
 use std::ops::Add;
 use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::SystemTime;
 
 use globset::{ GlobBuilder, GlobMatcher };
+use regex::Regex;
 
+use super::Cmd;
 use super::Resource;
 use super::Set;
+use super::res::up_to_date;
 
 //-- Unit ------------------------------------------------------------------------------------------
 
@@ -39,9 +44,54 @@ impl Unit {
            Unit::File(ref res) => res.link_from_inside(dir),
         }
     }
+
+    /// True if this unit is a File
+    pub fn is_file(&self) -> bool {
+        matches!(self, Unit::File(_))
+    }
+
+    /// True if this unit is a Dir
+    pub fn is_dir(&self) -> bool {
+        matches!(self, Unit::Dir(_))
+    }
+
+    /// The File this unit is or None if it is a Dir
+    pub fn as_file(&self) -> Option<&File> {
+        match self {
+            Unit::File(ref file) => Some(file),
+            Unit::Dir(_) => None,
+        }
+    }
+
+    /// The Dir this unit is or None if it is a File
+    pub fn as_dir(&self) -> Option<&Dir> {
+        match self {
+            Unit::Dir(ref dir) => Some(dir),
+            Unit::File(_) => None,
+        }
+    }
+}
+
+impl AsRef<Path> for Unit {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl std::fmt::Display for Unit {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+           Unit::Dir(ref res) => res.fmt(formatter),
+           Unit::File(ref res) => res.fmt(formatter),
+        }
+    }
 }
 
 impl Resource for Unit {
+    fn name(&self) -> String {
+        super::res::path_name(self.path())
+    }
+
     //TODO: test
     fn timestamp(&self) -> Option<SystemTime> {
         match self {
@@ -49,6 +99,17 @@ impl Resource for Unit {
            Unit::File(ref res) => res.timestamp(),
         }
     }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        vec![self.path().to_owned()]
+    }
+
+    fn fingerprint(&self) -> Option<Vec<u8>> {
+        match self {
+           Unit::Dir(ref res) => res.fingerprint(),
+           Unit::File(ref res) => res.fingerprint(),
+        }
+    }
 }
 
 //-- File ------------------------------------------------------------------------------------------
@@ -63,6 +124,8 @@ pub struct File {
 impl File {
 
     /// Create new File pointing to absolute file system `path`
+    ///
+    /// Conversions `From` paths and strings stop the build if the path is not absolute instead.
     pub fn new<P:AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
         match normalize(path.as_ref()) {
             Some(path) if path.is_absolute() => Ok(File { path }),
@@ -75,6 +138,19 @@ impl File {
         &self.path
     }
 
+    /// True if the file (or file a link by this name points to) exists, false if it does not or
+    /// it is a directory.
+    pub fn exists(&self) -> bool {
+        std::fs::metadata(&self.path).map(|m| m.is_file()).unwrap_or(false)
+    }
+
+    /// True if the file is up to date with `other` resource, following the rebuild rules of
+    /// [`mk_from`](trait.Resource.html#method.mk_from): false if the file does not exist or if
+    /// `other` has no timestamp (like when missing) or was modified after the file.
+    pub fn is_newer_than<R: Resource>(&self, other: &R) -> bool {
+        up_to_date(self.timestamp(), other)
+    }
+
     /// Creates the file using [`create`](#method.create) and returns itself or stops the build with
     /// informative error message.
     pub fn created(self) -> Self {
@@ -90,7 +166,7 @@ impl File {
 
     /// Creates (or truncates) the file and any missing directories on it's path in write only mode.
     pub fn create_result(&self) -> std::io::Result<std::fs::File> {
-        println!("Creating file: {}", self);
+        log!("Creating file: {}", self);
 
         if let Some(parent) = self.parent() {
             parent.create_result()?;
@@ -99,6 +175,36 @@ impl File {
         std::fs::File::create(&self.path)
     }
 
+    /// Opens the file for appending using [`append_result`](#method.append_result) or stops the
+    /// build with informative error message.
+    pub fn append(&self) -> std::fs::File {
+        self.append_result().expect(format!("Appending file {} FAILED", self).as_str())
+    }
+
+    /// Opens the file in append mode creating it and any missing directories on it's path if the
+    /// file does not exist yet.
+    pub fn append_result(&self) -> std::io::Result<std::fs::File> {
+        log!("Appending file: {}", self);
+
+        if let Some(parent) = self.parent() {
+            parent.create_result()?;
+        }
+
+        std::fs::OpenOptions::new().create(true).append(true).open(&self.path)
+    }
+
+    /// Appends bytes to the end of the file using
+    /// [`append_bytes_result`](#method.append_bytes_result) or stops the build with informative
+    /// error message.
+    pub fn append_bytes<P: AsRef<[u8]>>(&self, bytes: P) {
+        self.append_bytes_result(bytes).expect(format!("Appending file {} FAILED", self).as_str());
+    }
+
+    /// Appends bytes to the end of the file opened by [`append_result`](#method.append_result)
+    pub fn append_bytes_result<P: AsRef<[u8]>>(&self, bytes: P) -> std::io::Result<()> {
+        self.append_result()?.write_all(bytes.as_ref())
+    }
+
     /// Creating a link to this file from another directory with this file's name returning self
     /// or stopping the build with informative error message.
     ///
@@ -150,13 +256,13 @@ impl File {
     /// To allow overwriting existing link to a different file set `force` to `true` or linking to
     /// a different file will also fail.
     pub fn link_to_result(&self, to: &File, force: bool) -> std::io::Result<()> {
-        println!("Creating link {} -> {}", self, to);
+        log!("Creating link {} -> {}", self, to);
 
         if let Some(parent) = self.parent() {
             parent.create_result()?;
         }
 
-        if self.path.exists() {
+        if std::fs::symlink_metadata(&self.path).is_ok() {
             match std::fs::read_link(&self.path) {
                 Ok(target) if target != to.path && force => std::fs::remove_file(self.path())?,
                 Ok(target) if target == to.path => return Ok(()),
@@ -167,6 +273,103 @@ impl File {
         File::platform_make_link(&to.path, &self.path)
     }
 
+    /// Makes sure there is a symbolic link at this file path pointing to given target file `to` using
+    /// [`ensure_link_to_result`](#method.ensure_link_to_result) or stops the build with informative
+    /// error message.
+    pub fn ensure_link_to(&self, to: &File) {
+        self.ensure_link_to_result(to)
+            .expect(format!("Ensuring link {} -> {} FAILED", self, to).as_str())
+    }
+
+    /// Makes sure there is a symbolic link at this file path pointing to given target file `to`
+    /// creating any needed directories in the process.
+    ///
+    /// Missing link is created and a link to a different target is replaced, but an existing file
+    /// or directory by that name is never overwritten and linking fails instead.
+    pub fn ensure_link_to_result(&self, to: &File) -> std::io::Result<()> {
+        self.link_to_result(to, true)
+    }
+
+    /// Create a hard link at this file path to given target file `to` using
+    /// [`hard_link_to_result`](#method.hard_link_to_result) or stops the build with informative
+    /// error message.
+    pub fn hard_link_to(&self, to: &File) {
+        self.hard_link_to_result(to, false)
+            .expect(format!("Creating hard link {} -> {} FAILED", self, to).as_str())
+    }
+
+    /// Create a hard link at this file path to given target file `to` creating any needed
+    /// directories in the process. Unlike symbolic links, hard links need no special privileges on
+    /// Windows but the target must exist and be on the same file system.
+    ///
+    /// If a file or directory by that name already exists, linking will fail.
+    /// To allow replacing an existing file or link set `force` to `true`.
+    pub fn hard_link_to_result(&self, to: &File, force: bool) -> std::io::Result<()> {
+        log!("Creating hard link {} -> {}", self, to);
+
+        if let Some(parent) = self.parent() {
+            parent.create_result()?;
+        }
+
+        if let Ok(metadata) = std::fs::symlink_metadata(&self.path) {
+            if metadata.is_dir() || !force {
+                return Err(std::io::ErrorKind::AlreadyExists.into());
+            }
+            std::fs::remove_file(&self.path)?;
+        }
+
+        std::fs::hard_link(&to.path, &self.path)
+    }
+
+    /// Create a symbolic link at this file path to given target file `to` falling back to a hard
+    /// link using [`link_or_hard_link_to_result`](#method.link_or_hard_link_to_result) or stops the
+    /// build with informative error message.
+    pub fn link_or_hard_link_to(&self, to: &File) {
+        self.link_or_hard_link_to_result(to, false)
+            .expect(format!("Creating link {} -> {} FAILED", self, to).as_str())
+    }
+
+    /// Create a symbolic link at this file path to given target file `to` like
+    /// [`link_to_result`](#method.link_to_result) does, but creates a hard link instead when the
+    /// platform does not permit creating symbolic links (Windows without developer mode).
+    ///
+    /// Any other linking error is returned as is, without attempting a hard link.
+    pub fn link_or_hard_link_to_result(&self, to: &File, force: bool) -> std::io::Result<()> {
+        match self.link_to_result(to, force) {
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                self.hard_link_to_result(to, force)
+            }
+            result => result,
+        }
+    }
+
+    /// Copies this file to `dest` file using [`copy_to`](#method.copy_to) and returns itself or
+    /// stops the build with informative error message.
+    pub fn copied_to(self, dest: &File) -> Self {
+        self.copy_to(dest);
+        self
+    }
+
+    /// Copies this file to `dest` file using [`copy_to_result`](#method.copy_to_result) or stops
+    /// the build with informative error message.
+    pub fn copy_to(&self, dest: &File) {
+        self.copy_to_result(dest)
+            .expect(format!("Copying file {} -> {} FAILED", self, dest).as_str())
+    }
+
+    /// Copies this file's content and permissions to `dest` file creating any needed directories.
+    ///
+    /// Unlike linking, an existing `dest` file is overwritten.
+    pub fn copy_to_result(&self, dest: &File) -> std::io::Result<()> {
+        log!("Copying file {} -> {}", self, dest);
+
+        if let Some(parent) = dest.parent() {
+            parent.create_result()?;
+        }
+
+        std::fs::copy(&self.path, &dest.path).map(|_| ())
+    }
+
     /// Opens file's metadata using [`metadata_result`](#method.metadata_result) or stops the build
     /// with informative error message.
     pub fn metadata(&self) -> std::fs::Metadata {
@@ -189,25 +392,172 @@ impl File {
         std::fs::File::open(&self.path)
     }
 
+    /// Reads the entire content of the file using [`read_result`](#method.read_result) or stops the
+    /// build with informative error message
+    pub fn read(&self) -> Vec<u8> {
+        self.read_result().expect(format!("Reading file {} FAILED", self).as_str())
+    }
+
+    /// Reads the entire content of the file into a bytes vector
+    pub fn read_result(&self) -> std::io::Result<Vec<u8>> {
+        std::fs::read(&self.path)
+    }
+
+    /// Reads the entire content of the file using
+    /// [`read_to_string_result`](#method.read_to_string_result) or stops the build with informative
+    /// error message
+    pub fn read_to_string(&self) -> String {
+        self.read_to_string_result().expect(format!("Reading file {} FAILED", self).as_str())
+    }
+
+    /// Reads the entire content of the file into a string failing if it is not valid UTF-8
+    pub fn read_to_string_result(&self) -> std::io::Result<String> {
+        std::fs::read_to_string(&self.path)
+    }
+
     /// Writes the entire content to the file using [`rewrite_result`](#method.rewrite_result) or
     /// stops the build with informative error message
-    //TODO: test
     pub fn rewrite<P: AsRef<[u8]>>(&self, bytes: P) {
-        self.rewrite_result(bytes).expect(format!("Writing text {} FAILED", self).as_str())
+        self.rewrite_result(bytes).expect(format!("Writing text {} FAILED", self).as_str());
     }
 
     /// Writes the entire content to the file if it is different then the current one
-    /// creating the file if needed.
-    //TODO: test
-    pub fn rewrite_result<P: AsRef<[u8]>>(&self, bytes: P) -> std::io::Result<()> {
+    /// creating the file if needed and returning true if the file has been written.
+    ///
+    /// Content is written to a temporary file in the same directory first which is then renamed
+    /// over the file, so the file is never seen (or left by a killed build) half written. Existing
+    /// file's permissions are kept and if this is a link, the file it points to is replaced.
+    pub fn rewrite_result<P: AsRef<[u8]>>(&self, bytes: P) -> std::io::Result<bool> {
         let bytes = bytes.as_ref();
         if let Ok(old) = std::fs::read(&self.path) {
             if old == bytes {
-                return Ok(())
+                return Ok(false)
             }
         }
 
-        self.create().write_all(bytes)
+        log!("Creating file: {}", self);
+
+        if let Some(parent) = self.parent() {
+            parent.create_result()?;
+        }
+
+        let path = std::fs::canonicalize(&self.path).unwrap_or_else(|_| self.path.clone());
+        let (temp, mut file) = Self::create_temp(&path)?;
+        let written = file.write_all(bytes);
+        drop(file);
+
+        let written = written
+            .and_then(|_| match std::fs::metadata(&path) {
+                Ok(old) => std::fs::set_permissions(&temp, old.permissions()),
+                Err(_) => Ok(()),
+            })
+            .and_then(|_| std::fs::rename(&temp, &path));
+
+        if written.is_err() {
+            let _ = std::fs::remove_file(&temp);
+        }
+        written.map(|_| true)
+    }
+
+    /// Creates a new temporary file next to `path` named uniquely for each call within and across
+    /// processes, so concurrent rewrites of the same file do not write to the same temporary one
+    fn create_temp(path: &Path) -> std::io::Result<(PathBuf, std::fs::File)> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        loop {
+            let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+            let mut name = OsStr::new(".").to_owned();
+            name.push(path.file_name().unwrap_or_default());
+            name.push(format!(".{}-{}.tmp", std::process::id(), count));
+            let temp = path.with_file_name(name);
+            match std::fs::OpenOptions::new().write(true).create_new(true).open(&temp) {
+                Ok(file) => return Ok((temp, file)),
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Writes the `template` with `{{key}}` placeholders substituted by `vars` to the file using
+    /// [`rewrite_template_result`](#method.rewrite_template_result) or stops the build with
+    /// informative error message.
+    pub fn rewrite_template(&self, template: &str, vars: &HashMap<&str, String>) -> bool {
+        self.rewrite_template_result(template, vars)
+            .expect(format!("Writing template {} FAILED", self).as_str())
+    }
+
+    /// Writes the `template` with `{{key}}` placeholders substituted by `vars` to the file if it is
+    /// different then the current one using [`rewrite_result`](#method.rewrite_result).
+    ///
+    /// Placeholders with keys missing from `vars` or not terminated by `}}` are reported as errors
+    /// of [`ErrorKind::InvalidData`] kind naming the key, so typos fail loudly.
+    ///
+    /// [`ErrorKind::InvalidData`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidData
+    pub fn rewrite_template_result(&self, template: &str, vars: &HashMap<&str, String>)
+        -> std::io::Result<bool>
+    {
+        let invalid = |message| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+        let mut text = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find("{{") {
+            text.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after.find("}}").ok_or_else(|| {
+                let placeholder = after.lines().next().unwrap_or_default();
+                invalid(format!("Template placeholder {{{{{} NOT TERMINATED", placeholder))
+            })?;
+            let key = after[..end].trim();
+            let value = vars.get(key).ok_or_else(|| invalid(
+                format!("Template variable {} NOT FOUND", key)
+            ))?;
+            text.push_str(value);
+            rest = &after[end + 2..];
+        }
+        text.push_str(rest);
+
+        self.rewrite_result(text)
+    }
+
+    /// Writes the content to the file only if it does not exist yet using
+    /// [`write_default_result`](#method.write_default_result) or stops the build with informative
+    /// error message
+    pub fn write_default<P: AsRef<[u8]>>(&self, bytes: P) -> bool {
+        self.write_default_result(bytes).expect(format!("Writing default {} FAILED", self).as_str())
+    }
+
+    /// Writes the content to the file only if it does not exist yet creating any needed directories
+    /// returning true if the file has been written.
+    pub fn write_default_result<P: AsRef<[u8]>>(&self, bytes: P) -> std::io::Result<bool> {
+        if std::fs::symlink_metadata(&self.path).is_ok() {
+            return Ok(false);
+        }
+
+        log!("Creating default file: {}", self);
+
+        if let Some(parent) = self.parent() {
+            parent.create_result()?;
+        }
+
+        let mut file = std::fs::OpenOptions::new().write(true).create_new(true).open(&self.path)?;
+        file.write_all(bytes.as_ref())?;
+        Ok(true)
+    }
+
+    /// Removes the file using [`remove_result`](#method.remove_result) or stops the build with
+    /// informative error message
+    pub fn remove(&self) {
+        self.remove_result().expect(format!("Removing file {} FAILED", self).as_str())
+    }
+
+    /// Removes the file (or a link by this name) doing nothing if it does not exist
+    pub fn remove_result(&self) -> std::io::Result<()> {
+        if std::fs::symlink_metadata(&self.path).is_err() {
+            return Ok(());
+        }
+
+        log!("Removing file: {}", self);
+        std::fs::remove_file(&self.path)
     }
 
     /// Touches the file using [`touch`](#method.touch) and returns itself or stops the build with
@@ -226,7 +576,7 @@ impl File {
     /// Touches the file by updating it's modification time or creating an empty one if it does not
     /// exists yet including any needed directories.
     pub fn touch_result(&self) -> std::io::Result<()> {
-        println!("Touching file: {}", self);
+        log!("Touching file: {}", self);
 
         if !self.path.exists() {
             return self.create_result().map(|_|());
@@ -236,11 +586,41 @@ impl File {
         filetime::set_file_mtime(self.path.clone(), now)
     }
 
+    /// Marks the file as executable (or not) using
+    /// [`set_executable_result`](#method.set_executable_result) or stops the build with informative
+    /// error message.
+    pub fn set_executable(&self, yes: bool) {
+        self.set_executable_result(yes)
+            .expect(format!("Setting executable file {} FAILED", self).as_str())
+    }
+
+    /// Marks the file as executable (or not) for everybody allowed to read it on Unix, doing
+    /// nothing on Windows where executability is decided by file extension.
+    pub fn set_executable_result(&self, yes: bool) -> std::io::Result<()> {
+        log!("Setting executable file: {} {}", self, yes);
+        File::platform_set_executable(&self.path, yes)
+    }
+
     /// Returns parent directory
     fn parent(&self) -> Option<Dir> {
         self.path.parent().map(|parent| Dir { path: parent.to_owned() })
     }
 
+    #[cfg(not(windows))]
+    fn platform_set_executable(path: &Path, yes: bool) -> std::io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut permissions = std::fs::metadata(path)?.permissions();
+        let mode = permissions.mode();
+        permissions.set_mode(if yes { mode | (mode & 0o444) >> 2 } else { mode & !0o111 });
+        std::fs::set_permissions(path, permissions)
+    }
+
+    #[cfg(windows)]
+    fn platform_set_executable(_path: &Path, _yes: bool) -> std::io::Result<()> {
+        Ok(())
+    }
+
     #[cfg(not(windows))]
     fn platform_make_link<P: AsRef<Path>, Q: AsRef<Path>>(src: P, dst: Q) -> std::io::Result<()> {
         std::os::unix::fs::symlink(src, dst)
@@ -254,6 +634,10 @@ impl File {
 
 impl Resource for File {
 
+    fn name(&self) -> String {
+        super::res::path_name(&self.path)
+    }
+
    fn timestamp(&self) -> Option<SystemTime> {
         if let Ok(metadata) = self.metadata_result() {
             return metadata.modified().ok();
@@ -261,6 +645,14 @@ impl Resource for File {
 
         None
     }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        vec![self.path.clone()]
+    }
+
+    fn fingerprint(&self) -> Option<Vec<u8>> {
+        super::res::file_fingerprint(&self.path)
+    }
 }
 
 impl AsRef<File> for File {
@@ -271,13 +663,31 @@ impl AsRef<File> for File {
 
 impl AsRef<OsStr> for File {
     fn as_ref(&self) -> &OsStr {
-        &self.path.as_ref()
+        self.path.as_ref()
     }
 }
 
 impl AsRef<Path> for File {
     fn as_ref(&self) -> &Path {
-        &self.path.as_ref()
+        self.path.as_ref()
+    }
+}
+
+impl From<&Path> for File {
+    fn from(path: &Path) -> Self {
+        File::new(path).unwrap()
+    }
+}
+
+impl From<PathBuf> for File {
+    fn from(path: PathBuf) -> Self {
+        File::new(path).unwrap()
+    }
+}
+
+impl From<&str> for File {
+    fn from(path: &str) -> Self {
+        File::new(path).unwrap()
     }
 }
 
@@ -303,6 +713,30 @@ impl std::fmt::Display for File {
     }
 }
 
+impl PartialEq<Path> for File {
+    fn eq(&self, other: &Path) -> bool {
+        normalize(other).as_ref() == Some(&self.path)
+    }
+}
+
+impl PartialEq<PathBuf> for File {
+    fn eq(&self, other: &PathBuf) -> bool {
+        self == other.as_path()
+    }
+}
+
+impl PartialEq<File> for Path {
+    fn eq(&self, other: &File) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<File> for PathBuf {
+    fn eq(&self, other: &File) -> bool {
+        other == self
+    }
+}
+
 //-- Dir -------------------------------------------------------------------------------------------
 
 /// Resource representing file system directory
@@ -314,7 +748,8 @@ pub struct Dir {
 
 impl Dir {
 
-    /// Create new Dir pointing to absolute file system `path` panicking if failed
+    /// Create new Dir pointing to absolute file system `path` panicking if failed, the same as
+    /// conversions `From` paths and strings do
     pub fn new<P:AsRef<Path>>(path: P) -> Self {
         Dir::new_safe(path).unwrap()
     }
@@ -332,6 +767,30 @@ impl Dir {
         self.path.as_ref()
     }
 
+    /// True if the directory (or directory a link by this name points to) exists, false if it does
+    /// not or it is a file.
+    pub fn exists(&self) -> bool {
+        std::fs::metadata(&self.path).map(|m| m.is_dir()).unwrap_or(false)
+    }
+
+    /// True if the directory has no entries or does not exist (or can not be read) at all
+    pub fn is_empty(&self) -> bool {
+        std::fs::read_dir(&self.path).map(|mut entries| entries.next().is_none()).unwrap_or(true)
+    }
+
+    /// Number of entries (files, directories and links) directly inside the directory or 0 if it
+    /// does not exist (or can not be read)
+    pub fn entry_count(&self) -> usize {
+        std::fs::read_dir(&self.path).map(|entries| entries.count()).unwrap_or(0)
+    }
+
+    /// True if the directory is up to date with `other` resource, following the rebuild rules of
+    /// [`mk_from`](trait.Resource.html#method.mk_from): false if the directory does not exist or if
+    /// `other` has no timestamp (like when missing) or was modified after the directory.
+    pub fn is_newer_than<R: Resource>(&self, other: &R) -> bool {
+        up_to_date(self.timestamp(), other)
+    }
+
     /// Creates the directory using [`create`](#method.create) and returns itself or stops the build
     /// with informative error message.
     pub fn created(self) -> Self {
@@ -352,17 +811,51 @@ impl Dir {
 
     /// All directory content (files, directories and links) matching given `glob` file name pattern
     pub fn content<G:AsRef<str>>(&self, glob: G) -> DirContent<Unit> {
-        DirContent::new(self.path.clone(), glob)
+        DirContent::new(self.path.clone(), compile(true, glob, false))
     }
 
     /// All subdirectories and directory links matching given `glob` file name pattern
     pub fn dirs<G:AsRef<str>>(&self, glob: G) -> DirContent<Dir> {
-        DirContent::new(self.path.clone(), glob)
+        DirContent::new(self.path.clone(), compile(true, glob, false))
     }
 
     /// All files and file links matching given `glob` file name pattern
     pub fn files<G:AsRef<str>>(&self, glob: G) -> DirContent<File> {
-        DirContent::new(self.path.clone(), glob)
+        DirContent::new(self.path.clone(), compile(true, glob, false))
+    }
+
+    /// All files and file links with relative path matching given regular expression `pattern`.
+    ///
+    /// The pattern is matched against the path relative to this directory using `/` as separator
+    /// and, unlike globs, matches anywhere in the path unless anchored with `^` and `$`.
+    pub fn files_regex<P:AsRef<str>>(&self, pattern: P) -> DirContent<File> {
+        DirContent::new(self.path.clone(), compile_regex(true, pattern))
+    }
+
+    /// Counts of files, directories and links matching given `glob` file name pattern together with
+    /// total size of matching files, all gathered in a single directory walk.
+    pub fn stats<G:AsRef<str>>(&self, glob: G) -> DirStats {
+        let content = DirContent::<Unit>::new(self.path.clone(), compile(true, glob, false));
+        content.walkdir().fold(DirStats::default(), |mut stats, e| {
+            if e.path_is_symlink() {
+                stats.links += 1;
+            }
+
+            if e.file_type().is_dir() {
+                stats.dirs += 1;
+            } else if e.file_type().is_file() {
+                stats.files += 1;
+                stats.total_bytes += e.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+
+            stats
+        })
+    }
+
+    /// Total size in bytes of all files in the directory and it's subdirectories, counted by
+    /// [`DirContent::total_size`](struct.DirContent.html#method.total_size) of `files("**")`.
+    pub fn size(&self) -> u64 {
+        self.files("**").total_size()
     }
 
     /// Subdirectory at given relative `path`
@@ -447,7 +940,7 @@ impl Dir {
     /// To allow overwriting existing link to a different directory set `force` to `true` or linking
     /// to a different directory will also fail.
     pub fn link_to_result(&self, to: &Dir, force: bool) -> std::io::Result<()> {
-        println!("Creating link {} -> {}", self, to);
+        log!("Creating link {} -> {}", self, to);
 
         if let Some(parent) = self.parent() {
             parent.create_result()?;
@@ -481,7 +974,7 @@ impl Dir {
     /// Touches the directory by updating it's modification time or creating a new one if it does
     /// not exists yet including any needed directories.
     pub fn touch_result(&self) -> std::io::Result<()> {
-        println!("Touching dir: {}", self);
+        log!("Touching dir: {}", self);
 
         if !self.path.exists() {
             return self.create_result();
@@ -491,6 +984,125 @@ impl Dir {
         filetime::set_file_mtime(self.path.clone(), now)
     }
 
+    /// Copies this directory to `dest` directory using [`copy_to`](#method.copy_to) and returns
+    /// itself or stops the build with informative error message.
+    pub fn copied_to(self, dest: &Dir) -> Self {
+        self.copy_to(dest);
+        self
+    }
+
+    /// Copies this directory to `dest` directory using [`copy_to_result`](#method.copy_to_result)
+    /// or stops the build with informative error message.
+    pub fn copy_to(&self, dest: &Dir) {
+        self.copy_to_result(dest)
+            .expect(format!("Copying dir {} -> {} FAILED", self, dest).as_str())
+    }
+
+    /// Copies all of this directory's files and subdirectories to `dest` directory preserving their
+    /// relative paths and creating any needed directories.
+    ///
+    /// Content is merged into an existing `dest` directory overwriting files by the same name as
+    /// [`File::copy_to_result`](struct.File.html#method.copy_to_result) does. Links are copied as
+    /// links pointing to the same target instead of copying what they point to.
+    pub fn copy_to_result(&self, dest: &Dir) -> std::io::Result<()> {
+        log!("Copying dir {} -> {}", self, dest);
+        dest.create_result()?;
+
+        for unit in self.content("**").follow_links(false).sorted() {
+            let relative = unit.path().strip_prefix(&self.path).unwrap();
+            let target = dest.path.join(relative);
+
+            if std::fs::symlink_metadata(unit.path())?.file_type().is_symlink() {
+                if std::fs::symlink_metadata(&target).is_ok() {
+                    remove_entry(&target, false)?;
+                }
+                let link = std::fs::read_link(unit.path())?;
+                match unit {
+                    Unit::Dir(_) => Dir::platform_make_link(link, target)?,
+                    _ => File::platform_make_link(link, target)?,
+                }
+            } else {
+                match unit {
+                    Unit::Dir(_) => std::fs::create_dir_all(target)?,
+                    Unit::File(file) => file.copy_to_result(&File { path: target })?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the directory using [`remove_result`](#method.remove_result) or stops the build with
+    /// informative error message
+    pub fn remove(&self) {
+        self.remove_result().expect(format!("Removing dir {} FAILED", self).as_str())
+    }
+
+    /// Removes the directory with all of it's content doing nothing if it does not exist. A link by
+    /// this name is removed without touching the directory it points to.
+    pub fn remove_result(&self) -> std::io::Result<()> {
+        match std::fs::symlink_metadata(&self.path) {
+            Ok(metadata) => {
+                log!("Removing dir: {}", self);
+                remove_entry(&self.path, metadata.is_dir())
+            }
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Removes all of the directory content using [`clean_result`](#method.clean_result) or stops
+    /// the build with informative error message
+    pub fn clean(&self) {
+        self.clean_result().expect(format!("Cleaning dir {} FAILED", self).as_str())
+    }
+
+    /// Removes all of the directory content keeping the directory itself, creating it if it does
+    /// not exist yet.
+    pub fn clean_result(&self) -> std::io::Result<()> {
+        log!("Cleaning dir: {}", self);
+        if !self.path.exists() {
+            return self.create_result();
+        }
+
+        for entry in std::fs::read_dir(&self.path)? {
+            let path = entry?.path();
+            remove_entry(&path, std::fs::symlink_metadata(&path)?.is_dir())?;
+        }
+        Ok(())
+    }
+
+    /// Creates a new uniquely named subdirectory using [`temp_result`](#method.temp_result) or
+    /// stops the build with informative error message.
+    pub fn temp(&self, prefix: &str) -> Dir {
+        self.temp_result(prefix).expect(format!("Creating temp dir in {} FAILED", self).as_str())
+    }
+
+    /// Creates a new subdirectory named by `prefix` followed by process id and a counter, creating
+    /// this directory first if needed.
+    ///
+    /// Names left behind by earlier processes with the same id are skipped, so the returned
+    /// directory is always a new and empty one. It is not removed automatically, use
+    /// [`temp_scoped`](#method.temp_scoped) for that.
+    pub fn temp_result(&self, prefix: &str) -> std::io::Result<Dir> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        self.create_result()?;
+        loop {
+            let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+            let dir = self.dir(format!("{}{}-{}", prefix, std::process::id(), count));
+            match std::fs::create_dir(&dir.path) {
+                Ok(()) => return Ok(dir),
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Creates a new uniquely named subdirectory using [`temp`](#method.temp) that is removed with
+    /// all of it's content when the returned guard is dropped.
+    pub fn temp_scoped(&self, prefix: &str) -> TempDir {
+        TempDir { dir: Some(self.temp(prefix)) }
+    }
+
     /// Returns parent directory
     fn parent(&self) -> Option<Dir> {
         self.path.parent().map(|parent| Dir { path: parent.to_owned() })
@@ -515,17 +1127,39 @@ impl AsRef<Dir> for Dir {
 
 impl AsRef<OsStr> for Dir {
     fn as_ref(&self) -> &OsStr {
-        &self.path.as_ref()
+        self.path.as_ref()
     }
 }
 
 impl AsRef<Path> for Dir {
     fn as_ref(&self) -> &Path {
-        &self.path.as_ref()
+        self.path.as_ref()
+    }
+}
+
+impl From<&Path> for Dir {
+    fn from(path: &Path) -> Self {
+        Dir::new(path)
+    }
+}
+
+impl From<PathBuf> for Dir {
+    fn from(path: PathBuf) -> Self {
+        Dir::new(path)
+    }
+}
+
+impl From<&str> for Dir {
+    fn from(path: &str) -> Self {
+        Dir::new(path)
     }
 }
 
 impl Resource for Dir {
+    fn name(&self) -> String {
+        super::res::path_name(&self.path)
+    }
+
     fn timestamp(&self) -> Option<SystemTime> {
         if let Ok(metadata) = std::fs::metadata(&self.path) {
             return metadata.modified().ok();
@@ -533,6 +1167,14 @@ impl Resource for Dir {
 
         None
     }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        vec![self.path.clone()]
+    }
+
+    fn fingerprint(&self) -> Option<Vec<u8>> {
+        super::res::dir_fingerprint(&self.path)
+    }
 }
 
 impl Add<&Dir> for &Dir {
@@ -557,46 +1199,140 @@ impl std::fmt::Display for Dir {
     }
 }
 
+impl PartialEq<Path> for Dir {
+    fn eq(&self, other: &Path) -> bool {
+        normalize(other).as_ref() == Some(&self.path)
+    }
+}
+
+impl PartialEq<PathBuf> for Dir {
+    fn eq(&self, other: &PathBuf) -> bool {
+        self == other.as_path()
+    }
+}
+
+impl PartialEq<Dir> for Path {
+    fn eq(&self, other: &Dir) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<Dir> for PathBuf {
+    fn eq(&self, other: &Dir) -> bool {
+        other == self
+    }
+}
+
+/// Removes a directory with all of it's content or anything else including links to directories
+fn remove_entry(path: &Path, is_dir: bool) -> std::io::Result<()> {
+    if is_dir {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path).or_else(|_| std::fs::remove_dir(path))
+    }
+}
+
+//-- TempDir ---------------------------------------------------------------------------------------
+
+/// Guard of a temporary directory removing it with all of it's content when dropped, created by
+/// [`Dir::temp_scoped`](struct.Dir.html#method.temp_scoped)
+///
+/// Dereferences to the [`Dir`](struct.Dir.html) so it can be used like one. Removal errors are
+/// ignored since they can not stop the build from a destructor.
+///
+#[derive(Debug)]
+pub struct TempDir {
+    dir: Option<Dir>,
+}
+
+impl TempDir {
+
+    /// Disables removal of the directory returning it
+    pub fn keep(mut self) -> Dir {
+        self.dir.take().unwrap()
+    }
+}
+
+impl std::ops::Deref for TempDir {
+    type Target = Dir;
+
+    fn deref(&self) -> &Dir {
+        self.dir.as_ref().unwrap()
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if let Some(dir) = self.dir.take() {
+            let _ = dir.remove_result();
+        }
+    }
+}
+
+//-- DirStats -------------------------------------------------------------------------------------
+
+/// Directory entry counts returned by [`Dir::stats`](struct.Dir.html#method.stats)
+///
+/// Links are counted by their target type as files or directories and additionally as links.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct DirStats {
+    /// Number of files and file links
+    pub files: usize,
+    /// Number of directories and directory links
+    pub dirs: usize,
+    /// Number of links to either files or directories
+    pub links: usize,
+    /// Sum of all file sizes in bytes
+    pub total_bytes: u64,
+}
+
 //-- Path normalization ----------------------------------------------------------------------------
 
-fn normalize<P:AsRef<Path>>(subpath: P) -> Option<PathBuf> {
-    let result = PathBuf::from(subpath.as_ref()).components().fold(Some((PathBuf::new(), 0)), |r, c| {
-        match r {
-            None => None,
-            Some((mut path, depth)) => match c {
-                Component::Normal(value) => {
-                    path.push(value);
-                    Some((path, depth + 1))
-                }
-                Component::CurDir => {
-                    Some((path, depth))
-                }
-                Component::ParentDir => {
-                    if depth == 0 {
-                        return  None
-                    }
-                    path.pop();
-                    Some((path, depth - 1))
-                }
-                Component::RootDir => {
-                    path.push("/");
-                    Some((path, 0))
-                }
-                Component::Prefix(prefix) => {
-                    path.push(prefix.as_os_str());
-                    Some((path, -1))
+/// Normalizes `path` by folding `.` and `..` components without touching the file system,
+/// returning `None` if the path does not name any entry below it's root (or current directory).
+///
+/// Parent directory components may not escape above the start of the path, including any root or
+/// Windows prefix (drive or UNC share) it has, so `/foo/../..`, `C:\foo\..\..`, `C:..\foo` and
+/// `\\server\share\..` are all rejected just like the root `/` or an empty path are. Files and
+/// directories created by [`Dir::file`](struct.Dir.html#method.file) and
+/// [`Dir::dir`](struct.Dir.html#method.dir) use it to stay inside their parent directory.
+pub fn normalize<P:AsRef<Path>>(path: P) -> Option<PathBuf> {
+    let mut normal = PathBuf::new();
+    let mut depth = 0;
+
+    for component in path.as_ref().components() {
+        match component {
+            Component::Normal(value) => {
+                normal.push(value);
+                depth += 1;
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if depth == 0 {
+                    return None
                 }
+                normal.pop();
+                depth -= 1;
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                normal.push(component.as_os_str());
+                depth = 0;
             }
-        }
-    });
-
-    if let Some((path,depth)) = result {
-        if depth > 0 {
-            return Some(path)
         }
     }
 
-    return None;
+    if depth > 0 {
+        Some(normal)
+    } else {
+        None
+    }
 }
 
 //-- DirContent ------------------------------------------------------------------------------------
@@ -607,66 +1343,329 @@ fn normalize<P:AsRef<Path>>(subpath: P) -> Option<PathBuf> {
 /// Matching is done on two sets of patterns:
 ///  - entry matches if any of the inclusion patterns matches and
 ///  - none of the exclusion pattern matches
+///
+/// Directory links are followed unless they point back into the walked directory or to a target
+/// already visited through another link, so each directory is walked only once even with cycles.
+/// Such links are still matched as directory entries, only their content is not walked again.
+/// Following links can be turned off with [`follow_links`](#method.follow_links).
 #[derive(Clone, Debug)]
 pub struct DirContent<T> {
     path: PathBuf,
-    matchers: Vec<(GlobMatcher, bool)>,
+    matchers: Vec<(Matcher, bool)>,
+    follow: bool,
+    min_depth: Option<usize>,
+    max_depth: Option<usize>,
+    order: Order,
+    modified_after: Option<SystemTime>,
+    case_insensitive: bool,
+    #[cfg(feature = "ignore")]
+    gitignore: bool,
     phantom: PhantomData<T>,
 }
 
+#[derive(Clone, Debug)]
+enum Matcher {
+    Glob(GlobMatcher),
+    Regex(Regex),
+}
+
+impl Matcher {
+    fn is_match(&self, relative: &Path) -> bool {
+        match self {
+            Matcher::Glob(glob) => glob.is_match(relative),
+            Matcher::Regex(regex) => regex.is_match(&relative.iter()
+                .map(|part| part.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Order {
+    Walk,
+    Path,
+    Modified,
+}
+
 impl<T> DirContent<T> {
 
-    fn new<G:AsRef<str>>(path: PathBuf, glob: G) -> Self {
+    fn new(path: PathBuf, matcher: (Matcher, bool)) -> Self {
         DirContent {
             phantom: PhantomData,
             path,
-            matchers: vec![compile(true, glob)],
+            matchers: vec![matcher],
+            follow: true,
+            min_depth: None,
+            max_depth: None,
+            order: Order::Walk,
+            modified_after: None,
+            case_insensitive: false,
+            #[cfg(feature = "ignore")]
+            gitignore: false,
+        }
+    }
+
+    /// Skips entries ignored by `.gitignore` files (or not), like `target` or `node_modules`
+    /// directories, which is off by default. Requires the `ignore` feature.
+    ///
+    /// Rules of `.gitignore` files in the directory, it's subdirectories and parent directories up
+    /// to the root of the Git repository it is in are applied the way Git does, the nearest rule
+    /// deciding. The `.git` directory itself is always skipped.
+    #[cfg(feature = "ignore")]
+    pub fn respect_gitignore(mut self, yes: bool) -> Self {
+        self.gitignore = yes;
+        self
+    }
+
+    /// Matches glob patterns ignoring case (or not) like file systems of macOS and Windows do by
+    /// default, applying to already added patterns as well as those added later.
+    ///
+    /// Matching is case sensitive by default for builds to be reproducible across platforms.
+    /// Regular expression patterns are not affected, use the `(?i)` flag for them instead.
+    pub fn case_insensitive(mut self, yes: bool) -> Self {
+        self.case_insensitive = yes;
+        self.matchers = self.matchers.into_iter().map(|(matcher, incl)| match matcher {
+            Matcher::Glob(glob) => compile(incl, glob.glob().glob(), yes),
+            regex => (regex, incl),
+        }).collect();
+        self
+    }
+
+    /// Limits matching entries to those modified strictly after given `time`
+    pub fn modified_after(mut self, time: SystemTime) -> Self {
+        self.modified_after = Some(time);
+        self
+    }
+
+    /// Limits matching entries to those modified after given `resource` was, like an output built
+    /// from them. All entries match when the resource does not exist.
+    pub fn modified_since_resource<R: Resource>(self, resource: &R) -> Self {
+        match resource.timestamp() {
+            Some(time) => self.modified_after(time),
+            None => self,
         }
     }
 
+    /// Yields entries sorted by their relative path instead of the file system's arbitrary order,
+    /// making any output generated from the entries reproducible.
+    pub fn sorted(mut self) -> Self {
+        self.order = Order::Path;
+        self
+    }
+
+    /// Yields entries sorted by their modification time, oldest first, with entries modified at
+    /// the same time sorted by their relative path.
+    pub fn sorted_by_mtime(mut self) -> Self {
+        self.order = Order::Modified;
+        self
+    }
+
+    /// Limits walking to entries at most `depth` levels below the directory, with direct children
+    /// being at depth 1. Patterns still match the entire relative path, so `**` limited to depth 1
+    /// matches the same entries as `*`.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Skips entries less than `depth` levels below the directory, with direct children being at
+    /// depth 1. Directories above this depth are still walked, just not matched.
+    pub fn min_depth(mut self, depth: usize) -> Self {
+        self.min_depth = Some(depth);
+        self
+    }
+
+    /// Sets whether links are followed and directories they point to walked (the default) or
+    /// links are treated as opaque entries matching as a file or directory by their target.
+    pub fn follow_links(mut self, yes: bool) -> Self {
+        self.follow = yes;
+        self
+    }
+
     /// Add exlusion pattern reducing the number of matching entries
     pub fn exclude<G:AsRef<str>>(mut self, glob: G) -> Self {
-        self.matchers.push(compile(false, glob));
+        self.matchers.push(compile(false, glob, self.case_insensitive));
         self
     }
 
     /// Add inclusion pattern increasing the number of matching entries
     pub fn include<G:AsRef<str>>(mut self, glob: G) -> Self {
-        self.matchers.push(compile(true, glob));
+        self.matchers.push(compile(true, glob, self.case_insensitive));
+        self
+    }
+
+    /// Add multiple exlusion patterns at once, same as calling [`exclude`](#method.exclude) for
+    /// each of them
+    pub fn exclude_all<I, G>(mut self, globs: I) -> Self
+        where I: IntoIterator<Item=G>, G: AsRef<str>
+    {
+        let case_insensitive = self.case_insensitive;
+        self.matchers.extend(globs.into_iter().map(|glob| compile(false, glob, case_insensitive)));
+        self
+    }
+
+    /// Add multiple inclusion patterns at once, same as calling [`include`](#method.include) for
+    /// each of them
+    pub fn include_all<I, G>(mut self, globs: I) -> Self
+        where I: IntoIterator<Item=G>, G: AsRef<str>
+    {
+        let case_insensitive = self.case_insensitive;
+        self.matchers.extend(globs.into_iter().map(|glob| compile(true, glob, case_insensitive)));
+        self
+    }
+
+    /// Add exlusion regular expression `pattern` reducing the number of matching entries, see
+    /// [`Dir::files_regex`](struct.Dir.html#method.files_regex) for how paths are matched
+    pub fn exclude_regex<P:AsRef<str>>(mut self, pattern: P) -> Self {
+        self.matchers.push(compile_regex(false, pattern));
+        self
+    }
+
+    /// Add inclusion regular expression `pattern` increasing the number of matching entries, see
+    /// [`Dir::files_regex`](struct.Dir.html#method.files_regex) for how paths are matched
+    pub fn include_regex<P:AsRef<str>>(mut self, pattern: P) -> Self {
+        self.matchers.push(compile_regex(true, pattern));
         self
     }
 
-    fn walkdir(&self) -> impl Iterator<Item=walkdir::DirEntry> {
+    fn walkdir(&self) -> Box<dyn Iterator<Item=walkdir::DirEntry>> {
         let root = self.path.clone();
         let matchers = self.matchers.clone();
-        walkdir::WalkDir::new(&self.path)
-            .follow_links(true)
-            .into_iter()
-            .filter_map(|e| e.ok())
+        let modified_after = self.modified_after;
+        let canonical = self.path.canonicalize().ok();
+        let mut visited = HashSet::new();
+        #[cfg(feature = "ignore")]
+        let mut gitignores = if self.gitignore { Some(Gitignores::new(&self.path)) } else { None };
+        let mut walkdir = walkdir::WalkDir::new(&self.path).follow_links(self.follow);
+        if let Some(depth) = self.min_depth {
+            walkdir = walkdir.min_depth(depth);
+        }
+        if let Some(depth) = self.max_depth {
+            walkdir = walkdir.max_depth(depth);
+        }
+        if self.order != Order::Walk {
+            walkdir = walkdir.sort_by_file_name();
+        }
+        let mut walk = walkdir.into_iter();
+        let walked = std::iter::from_fn(move || loop {
+            let e = match walk.next()? {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+            #[cfg(feature = "ignore")]
+            if e.depth() > 0 && gitignores.as_mut().is_some_and(|rules| rules.is_ignored(&e)) {
+                if e.file_type().is_dir() {
+                    walk.skip_current_dir();
+                }
+                continue;
+            }
+            if e.depth() == 0 || !e.path_is_symlink() || !e.file_type().is_dir() {
+                return Some(e);
+            }
+            let seen = match e.path().canonicalize() {
+                Ok(target) if canonical.as_ref().is_some_and(|c| target.starts_with(c)) => true,
+                Ok(target) => !visited.insert(target),
+                Err(_) => false,
+            };
+            if seen {
+                walk.skip_current_dir();
+            }
+            return Some(e);
+        });
+        let entries = walked
             .filter(move |e| e.depth() > 0 && {
                 let relative = e.path().strip_prefix(&root).unwrap();
                 let mut matched = false;
                 for matcher in &matchers {
                     if matcher.0.is_match(relative) {
-                        matched = matcher.1 || return false;
+                        if !matcher.1 {
+                            return false;
+                        }
+                        matched = true;
                     }
                 }
                 matched
             })
+            .filter(move |e| modified_after.is_none_or(|after| {
+                e.metadata().ok().and_then(|m| m.modified().ok()).is_some_and(|time| time > after)
+            }));
+
+        if self.order != Order::Modified {
+            return Box::new(entries);
+        }
+
+        let mut entries = entries
+            .map(|e| (e.metadata().ok().and_then(|m| m.modified().ok()), e))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(modified, _)| *modified);
+        Box::new(entries.into_iter().map(|(_, e)| e))
+    }
+}
+
+fn is_dir(entry: &walkdir::DirEntry) -> bool {
+    entry.file_type().is_dir() || entry.path_is_symlink() && entry.path().is_dir()
+}
+
+fn is_file(entry: &walkdir::DirEntry) -> bool {
+    entry.file_type().is_file() || entry.path_is_symlink() && entry.path().is_file()
+}
+
+/// Rules of `.gitignore` files parsed on demand while walking a directory
+#[cfg(feature = "ignore")]
+struct Gitignores {
+    top: PathBuf,
+    parsed: HashMap<PathBuf, ignore::gitignore::Gitignore>,
+}
+
+#[cfg(feature = "ignore")]
+impl Gitignores {
+    fn new(root: &Path) -> Self {
+        let top = root.ancestors().find(|dir| dir.join(".git").exists()).unwrap_or(root);
+        Gitignores { top: top.to_owned(), parsed: HashMap::new() }
+    }
+
+    fn is_ignored(&mut self, entry: &walkdir::DirEntry) -> bool {
+        if entry.file_name() == ".git" {
+            return true;
+        }
+
+        for dir in entry.path().ancestors().skip(1) {
+            let rules = self.parsed.entry(dir.to_owned())
+                .or_insert_with(|| ignore::gitignore::Gitignore::new(dir.join(".gitignore")).0);
+            match rules.matched(entry.path(), is_dir(entry)) {
+                ignore::Match::Ignore(_) => return true,
+                ignore::Match::Whitelist(_) => return false,
+                ignore::Match::None if dir == self.top => return false,
+                ignore::Match::None => {}
+            }
+        }
+        false
     }
 }
 
-fn compile<G:AsRef<str>>(incl: bool, glob: G) -> (GlobMatcher, bool) {
+fn compile<G:AsRef<str>>(incl: bool, glob: G, case_insensitive: bool) -> (Matcher, bool) {
     (
-        GlobBuilder::new(glob.as_ref()).literal_separator(true).build().unwrap().compile_matcher(),
+        Matcher::Glob(GlobBuilder::new(glob.as_ref())
+            .literal_separator(true)
+            .case_insensitive(case_insensitive)
+            .build()
+            .unwrap()
+            .compile_matcher()
+        ),
         incl
     )
 }
 
+fn compile_regex<P:AsRef<str>>(incl: bool, pattern: P) -> (Matcher, bool) {
+    (Matcher::Regex(Regex::new(pattern.as_ref()).unwrap()), incl)
+}
+
 impl DirContent<Unit> {
     fn iter(&self) -> Box<dyn Iterator<Item=Unit>> {
         Box::new(self.walkdir().map(|e|
-            if e.file_type().is_dir() {
+            if is_dir(&e) {
                 Unit::Dir( Dir { path: e.path().to_owned() })
             } else {
                 Unit::File( File { path: e.path().to_owned() })
@@ -678,7 +1677,7 @@ impl DirContent<Unit> {
 impl DirContent<Dir> {
     fn iter(&self) -> Box<dyn Iterator<Item=Dir>> {
         Box::new(self.walkdir().filter_map(|e|
-            if e.file_type().is_dir() {
+            if is_dir(&e) {
                 Some(Dir { path: e.path().to_owned() })
             } else {
                 None
@@ -690,13 +1689,105 @@ impl DirContent<Dir> {
 impl DirContent<File> {
     fn iter(&self) -> Box<dyn Iterator<Item=File>> {
         Box::new(self.walkdir().filter_map(|e|
-            if e.file_type().is_file() {
+            if is_file(&e) {
                 Some(File { path: e.path().to_owned() })
             } else {
                 None
             }
         ))
     }
+
+    /// Total size in bytes of all matching files.
+    ///
+    /// Files reached through links are counted by the size of their target, each target only once
+    /// even when matched by more paths, like the file itself and a link to it.
+    pub fn total_size(&self) -> u64 {
+        let mut counted = HashSet::new();
+        self.iter()
+            .filter(|file| counted.insert(std::fs::canonicalize(&file.path).unwrap_or_default()))
+            .map(|file| std::fs::metadata(&file.path).map(|m| m.len()).unwrap_or(0))
+            .sum()
+    }
+
+    /// Runs a command for each matching file that is newer than it's output returning the number
+    /// of commands ran.
+    ///
+    /// Output file for each input is provided by the `output` function and the command to run by
+    /// `make_cmd` function called with input and output file. Output's directory is created before
+    /// running the command using [`Cmd::run`](struct.Cmd.html#method.run) which stops the build if
+    /// any of the commands fails.
+    pub fn for_each_run<O, C>(self, output: O, make_cmd: C) -> usize
+        where O: Fn(&File) -> File, C: Fn(&File, &File) -> Cmd
+    {
+        self.iter().fold(0, |count, input| {
+            let output = output(&input);
+            let ran = output.mk_from("Running per file command", &input, || {
+                if let Some(parent) = output.parent() {
+                    parent.create();
+                }
+                make_cmd(&input, &output).run();
+            });
+            count + ran as usize
+        })
+    }
+}
+
+impl<T> DirContent<T> where T: AsRef<Path> + Clone, DirContent<T>: IntoIterator<Item=T> {
+
+    /// First matching entry in walk (or sort) order or None if there are no matches
+    pub fn first(&self) -> Option<T> {
+        self.clone().into_iter().next()
+    }
+
+    /// The only matching entry using [`single_result`](#method.single_result) or stops the build
+    /// with informative error message.
+    pub fn single(&self) -> T {
+        self.single_result()
+            .expect(format!("Finding single entry in {} FAILED", self.path.display()).as_str())
+    }
+
+    /// The only matching entry or an error if there is none or more of them, listing those found
+    pub fn single_result(&self) -> Result<T, Box<dyn std::error::Error>> {
+        let mut entries = self.clone().into_iter().collect::<Vec<_>>();
+        match entries.len() {
+            1 => Ok(entries.remove(0)),
+            0 => Err(format!("No entry of {} matches", self.path.display()).into()),
+            count => {
+                let found = entries.iter()
+                    .map(|entry| entry.as_ref().display().to_string())
+                    .collect::<Vec<_>>();
+                Err(format!("{} entries match: {}", count, found.join(", ")).into())
+            }
+        }
+    }
+
+    /// Paths of all matching entries relative to given `root` directory which can be any ancestor
+    /// of this content's directory.
+    ///
+    /// Entries not located under the `root` are skipped with a Cargo warning, use
+    /// [`relative_paths_result`](#method.relative_paths_result) to treat them as an error instead.
+    pub fn relative_paths(&self, root: &Dir) -> Vec<PathBuf> {
+        self.clone().into_iter().filter_map(|entry| match entry.as_ref().strip_prefix(&root.path) {
+            Ok(path) => Some(path.to_owned()),
+            Err(_) => {
+                println!("cargo:warning=Path {} is not under {}", entry.as_ref().display(), root);
+                None
+            }
+        }).collect()
+    }
+
+    /// Paths of all matching entries relative to given `root` directory or an error naming the
+    /// first entry not located under the `root`.
+    pub fn relative_paths_result(&self, root: &Dir)
+        -> Result<Vec<PathBuf>, Box<dyn std::error::Error>>
+    {
+        self.clone().into_iter().map(|entry| match entry.as_ref().strip_prefix(&root.path) {
+            Ok(path) => Ok(path.to_owned()),
+            Err(_) => {
+                Err(format!("Path {} is not under {}", entry.as_ref().display(), root).into())
+            }
+        }).collect()
+    }
 }
 
 impl<T> AsRef<DirContent<T>> for DirContent<T> {
@@ -732,20 +1823,63 @@ impl IntoIterator for DirContent<File> {
     }
 }
 
+impl<T> From<DirContent<T>> for Set<T> where DirContent<T>: IntoIterator<Item=T> {
+    /// Walks the directory once, snapshotting matching entries into an owned set
+    fn from(content: DirContent<T>) -> Self {
+        content.into_iter().collect::<Vec<_>>().into()
+    }
+}
+
 impl Resource for DirContent<Dir> {
+    fn name(&self) -> String {
+        format!("content of {}", super::res::path_name(&self.path))
+    }
+
     fn timestamp(&self) -> Option<SystemTime> {
         super::res::timestamp(self.iter())
     }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        self.iter().map(|e| e.path().to_owned()).collect()
+    }
+
+    fn fingerprint(&self) -> Option<Vec<u8>> {
+        super::res::entries_fingerprint(&self.path, self.iter().map(|e| e.path().to_owned()))
+    }
 }
 
 impl Resource for DirContent<File> {
+    fn name(&self) -> String {
+        format!("content of {}", super::res::path_name(&self.path))
+    }
+
     fn timestamp(&self) -> Option<SystemTime> {
         super::res::timestamp(self.iter())
     }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        self.iter().map(|e| e.path().to_owned()).collect()
+    }
+
+    fn fingerprint(&self) -> Option<Vec<u8>> {
+        super::res::entries_fingerprint(&self.path, self.iter().map(|e| e.path().to_owned()))
+    }
 }
 
 impl Resource for DirContent<Unit> {
+    fn name(&self) -> String {
+        format!("content of {}", super::res::path_name(&self.path))
+    }
+
     fn timestamp(&self) -> Option<SystemTime> {
         super::res::timestamp(self.iter())
     }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        self.iter().map(|e| e.path().to_owned()).collect()
+    }
+
+    fn fingerprint(&self) -> Option<Vec<u8>> {
+        super::res::entries_fingerprint(&self.path, self.iter().map(|e| e.path().to_owned()))
+    }
 }
\ No newline at end of file
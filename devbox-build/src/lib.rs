@@ -10,6 +10,11 @@
 //! you do want to recover from errors or implement a better error reporting most method have a twin
 //! method suffixed with '_result' that return `Result` values instead.
 //!
+//! Operations log what they do (like `Creating file: ...`) to the build script output, which can
+//! be silenced with [`set_verbosity`](fn.set_verbosity.html) keeping Cargo directives and errors.
+//! With the `log` feature enabled they are logged through the `log` crate instead, so they can be
+//! filtered by loggers like `env_logger` with `RUST_LOG`, while Cargo directives are still printed.
+//!
 //! # To install via umbrella devbox crate
 //!
 //! ```toml
@@ -65,7 +70,7 @@
 //!     //-- Package webapp into server binary as Rust source code ------------------------
 //!
 //!     webrs.mk_from("Embed WebApp build into binary", &webwrk_dst, || {
-//!         let mappings = webwrk_dst.files("**").into_iter().map(|file|
+//!         let mappings = webwrk_dst.files("**").sorted().into_iter().map(|file|
 //!             format!(r#""{}" => Some(include_bytes!("{}")),"#,
 //!                 file.path().strip_prefix(&webwrk_dst.path()).unwrap().to_str().unwrap(),
 //!                 file.path().to_str().unwrap())
@@ -83,12 +88,17 @@
 //! }
 //! ```
 
+#![allow(clippy::expect_fun_call)]
+
+#[macro_use]
+mod log;
 mod build;
 mod cmd;
 mod fs;
 mod res;
 
-pub use build::Build;
-pub use cmd::Cmd;
-pub use fs::{File, Dir, Unit};
-pub use res::{Resource, Set};
+pub use build::{Build, BuildError, Profile};
+pub use cmd::{Cmd, CmdError, Pipeline};
+pub use fs::{normalize, File, Dir, DirStats, TempDir, Unit};
+pub use log::{set_verbosity, verbosity, Verbosity};
+pub use res::{build_parallel, newest_input, Always, BuildStep, Deferred, Resource, Set, Virtual};
@@ -0,0 +1,54 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+//-- Verbosity -------------------------------------------------------------------------------------
+
+/// Amount of build chatter printed by operations like creating files or executing commands
+///
+/// Only routine operation logging is affected, Cargo directives (including warnings) and error
+/// messages stopping the build are always printed. With the `log` feature enabled, operations are
+/// logged through the `log` crate at info level, further filtered by the installed logger.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Verbosity {
+    /// No routine operation logging
+    Quiet,
+    /// Logging of every operation like `Creating file: ...`, the default
+    Verbose,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Verbose as u8);
+
+/// Sets verbosity of all devbox operations in this build process
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+/// Current verbosity of devbox operations
+pub fn verbosity() -> Verbosity {
+    if VERBOSITY.load(Ordering::Relaxed) == Verbosity::Quiet as u8 {
+        Verbosity::Quiet
+    } else {
+        Verbosity::Verbose
+    }
+}
+
+/// Emits routine operation log line unless [`Verbosity::Quiet`] is set
+macro_rules! log {
+    ($($arg:tt)*) => {
+        if $crate::verbosity() == $crate::Verbosity::Verbose {
+            $crate::log::emit(format_args!($($arg)*));
+        }
+    };
+}
+
+/// Logs the line at info level through the `log` crate
+#[cfg(feature = "log")]
+pub(crate) fn emit(line: std::fmt::Arguments) {
+    ::log::info!(target: "devbox_build", "{}", line);
+}
+
+/// Prints the line to the build script output
+#[cfg(not(feature = "log"))]
+pub(crate) fn emit(line: std::fmt::Arguments) {
+    println!("{}", line);
+}
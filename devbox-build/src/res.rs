@@ -1,4 +1,7 @@
-use std::{fmt::Debug, ops::Add, time::SystemTime};
+use std::{cell::OnceCell, fmt::{self, Debug}, ops::Add, path::{Path, PathBuf}, sync::Mutex};
+use std::time::SystemTime;
+
+use sha2::{Digest, Sha256};
 
 //-- Resource --------------------------------------------------------------------------------------
 
@@ -15,41 +18,134 @@ use std::{fmt::Debug, ops::Add, time::SystemTime};
 ///
 pub trait Resource : Debug {
 
-    /// Name of the resource used for logging and error reporting
-    //fn name(&self) -> &str;
+    /// Timestamp of the resource when used as an output of a build step, which is the same as
+    /// [`timestamp()`](#tymethod.timestamp) by default.
+    ///
+    /// Resource collections like [`Set`](struct.Set.html) use the oldest of their resources
+    /// instead, or None if any of them has no timestamp, so that a build step producing several
+    /// outputs is ran when any of them is missing or out of date.
+    fn output_timestamp(&self) -> Option<SystemTime> {
+        self.timestamp()
+    }
+
+    /// Short name of the resource used for logging, like a file name or number of resources in a
+    /// set. Debug representation by default.
+    fn name(&self) -> String {
+        format!("{:?}", self)
+    }
 
     /// Return resource timestamp. Can be None for input resources that should be considered as
     /// changed in every build run or output resources that do not exists yet.
     fn timestamp(&self) -> Option<SystemTime>;
 
+    /// Paths of files and directories this resource consists of, used by
+    /// [`rerun_if_changed()`](#method.rerun_if_changed). Empty by default.
+    fn paths(&self) -> Vec<PathBuf> {
+        vec![]
+    }
+
+    /// Content based fingerprint of the resource used as an alternative to timestamps by
+    /// [`mk_from_hashed()`](#method.mk_from_hashed). None (the default) means the resource should
+    /// be considered as changed in every build run.
+    fn fingerprint(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// True if outputs built from this resource must be rebuilt regardless of timestamps, like for
+    /// the [`Always`](struct.Always.html) resource. False by default.
+    fn forces_rebuild(&self) -> bool {
+        false
+    }
+
+    /// Instructs Cargo to rerun the build script when any of the resource [`paths`](#method.paths)
+    /// changes by printing `cargo:rerun-if-changed` directive for each of them.
+    fn rerun_if_changed(&self) {
+        for path in self.paths() {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+
     /// Build the resource form a given `src` resource as a side product of given function `by`
     /// respecting resource timestamps meaning that function `by` will only be ran if the output
     /// needs to be build.
     ///
+    /// Returns true if the function `by` has been ran, allowing to chain follow up steps only when
+    /// the output has actually been rebuilt.
+    ///
     /// This method forces the `by` function to handle any errors on it's own and stop Cargo build
     /// using a panic. To propagate the error, use [`mk_from_result()`](#method.mk_from_result)
     ///
-    //TODO: test
-    fn mk_from<F, R, S>(&self, description: &str, src: S, by: F)
-        where R:Resource, S:AsResource<R>, F: FnOnce() -> ()
+    fn mk_from<F, R, S>(&self, description: &str, src: S, by: F) -> bool
+        where Self: Sized, R:Resource, S:AsResource<R>, F: FnOnce()
+    {
+        if needs_build(self, src.as_res(), description) {
+            by();
+            return true;
+        }
+        false
+    }
+
+    /// Same as [`mk_from()`](#method.mk_from) but also instructing Cargo to rerun the build script
+    /// when `src` changes using [`rerun_if_changed()`](#method.rerun_if_changed) on it.
+    fn mk_from_watched<F, R, S>(&self, description: &str, src: S, by: F) -> bool
+        where Self: Sized, R:Resource, S:AsResource<R>, F: FnOnce()
+    {
+        src.as_res().rerun_if_changed();
+        self.mk_from(description, src, by)
+    }
+
+    /// Same as [`mk_from()`](#method.mk_from) but comparing `src`
+    /// [`fingerprint`](#method.fingerprint) to the one stored when this resource was last built
+    /// instead of comparing timestamps.
+    ///
+    /// This avoids rebuilds when only timestamps change (like on `git checkout` or restoring CI
+    /// caches) and catches content changes that did not move them. Fingerprints are stored in
+    /// `devbox-fingerprints` directory inside Cargo's `OUT_DIR` (or system temp directory if not
+    /// set) in a file named by the hash of this resource's paths and the `description`.
+    fn mk_from_hashed<F, R, S>(&self, description: &str, src: S, by: F) -> bool
+        where Self: Sized, R:Resource, S:AsResource<R>, F: FnOnce()
+    {
+        let dir = std::env::var_os("OUT_DIR").map(PathBuf::from).unwrap_or_else(std::env::temp_dir);
+        self.mk_from_hashed_in(dir, description, src, by)
+    }
+
+    /// Same as [`mk_from_hashed()`](#method.mk_from_hashed) but storing fingerprints in
+    /// `devbox-fingerprints` directory inside given `dir` instead of Cargo's `OUT_DIR`.
+    fn mk_from_hashed_in<D, F, R, S>(&self, dir: D, description: &str, src: S, by: F) -> bool
+        where Self: Sized, D: AsRef<Path>, R:Resource, S:AsResource<R>, F: FnOnce()
     {
         let src = src.as_res();
-        let target_time = self.timestamp();
-        if target_time == None || src.timestamp() > target_time {
-            println!("Building: {:?} from {:?}: {}", self, src, description);
+        let mut key = Sha256::new();
+        for path in self.paths() {
+            key.update(path.to_string_lossy().as_bytes());
+            key.update([0]);
+        }
+        key.update(description.as_bytes());
+        let stamp = dir.as_ref().join("devbox-fingerprints").join(hex(&key.finalize()));
+
+        let fingerprint = src.fingerprint();
+        let stored = std::fs::read(&stamp).ok();
+        let changed = src.forces_rebuild() || fingerprint.is_none() || fingerprint != stored;
+        if self.output_timestamp().is_none() || changed {
+            log!("Building: {} from {}: {}", self.name(), src.name(), description);
             by();
+
+            if let Some(fingerprint) = fingerprint {
+                std::fs::create_dir_all(stamp.parent().unwrap())
+                    .and_then(|_| std::fs::write(&stamp, fingerprint))
+                    .expect(format!("Writing fingerprint {} FAILED", stamp.display()).as_str());
+            }
+            return true;
         }
+        false
     }
 
     /// Same as [`mk_from()`](#method.mk_from) with error propagation
     //TODO: test
     fn mk_from_result<E, F, R, S>(&self, description: &str, src: S, by: F) -> Result<(), E>
-        where R:Resource, S:AsRef<R>, F: FnOnce() -> Result<(), E>
+        where Self: Sized, R:Resource, S:AsResource<R>, F: FnOnce() -> Result<(), E>
     {
-        let src = src.as_ref();
-        let target_time = self.timestamp();
-        if target_time == None || src.timestamp() > target_time {
-            println!("Building: {:?} from {:?}: {}", self, src, description);
+        if needs_build(self, src.as_res(), description) {
             return by()
         }
 
@@ -63,7 +159,7 @@ pub trait AsResource<R> {
 
 impl<R> AsResource<R> for R where R:Resource {
     fn as_res(&self) -> &R {
-        &self
+        self
     }
 }
 
@@ -78,9 +174,123 @@ impl<R> AsResource<R> for &R where R:Resource {
 impl<R> Resource for Vec<R>
     where R:Resource
 {
+    fn name(&self) -> String {
+        format!("<{} resources>", self.len())
+    }
+
+    fn output_timestamp(&self) -> Option<SystemTime> {
+        self.iter().map(|res| res.output_timestamp()).min().flatten()
+    }
+
     fn timestamp(&self) -> Option<SystemTime> {
         timestamp(self.iter())
     }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        self.iter().flat_map(|res| res.paths()).collect()
+    }
+
+    fn forces_rebuild(&self) -> bool {
+        self.iter().any(|res| res.forces_rebuild())
+    }
+
+    fn fingerprint(&self) -> Option<Vec<u8>> {
+        let mut hasher = Sha256::new();
+        for res in self.iter() {
+            hasher.update(res.fingerprint()?);
+        }
+        Some(hasher.finalize().to_vec())
+    }
+}
+
+//--- Resource for tuples --------------------------------------------------------------------------
+
+/// Tuples of resource references combine resources of different types while keeping their own
+/// timestamp logic, so `(&src_dir, &config_file)` can be used as input of a build step the same way
+/// as a [`Set`](struct.Set.html) of resources.
+macro_rules! tuple_resource {
+    ($($res:ident $idx:tt),+) => {
+        impl<$($res),+> Resource for ($(&$res,)+)
+            where $($res: Resource + ?Sized),+
+        {
+            fn name(&self) -> String {
+                format!("<{} resources>", [$(stringify!($res)),+].len())
+            }
+
+            fn output_timestamp(&self) -> Option<SystemTime> {
+                [$(self.$idx.output_timestamp()),+].iter().min().copied().flatten()
+            }
+
+            fn timestamp(&self) -> Option<SystemTime> {
+                [$(self.$idx.timestamp()),+].iter().max().copied().flatten()
+            }
+
+            fn paths(&self) -> Vec<PathBuf> {
+                let mut paths = Vec::new();
+                $(paths.extend(self.$idx.paths());)+
+                paths
+            }
+
+            fn forces_rebuild(&self) -> bool {
+                $(self.$idx.forces_rebuild())||+
+            }
+
+            fn fingerprint(&self) -> Option<Vec<u8>> {
+                let mut hasher = Sha256::new();
+                $(hasher.update(self.$idx.fingerprint()?);)+
+                Some(hasher.finalize().to_vec())
+            }
+        }
+    }
+}
+
+tuple_resource!(A 0, B 1);
+tuple_resource!(A 0, B 1, C 2);
+tuple_resource!(A 0, B 1, C 2, D 3);
+tuple_resource!(A 0, B 1, C 2, D 3, E 4);
+tuple_resource!(A 0, B 1, C 2, D 3, E 4, F 5);
+
+//--- Resource for Path ----------------------------------------------------------------------------
+
+/// Plain paths are resources as well, of a file or directory whichever exists, so they can be used
+/// without wrapping them into [`File`](struct.File.html) or [`Dir`](struct.Dir.html)
+impl Resource for Path {
+    fn name(&self) -> String {
+        path_name(self)
+    }
+
+    fn timestamp(&self) -> Option<SystemTime> {
+        std::fs::metadata(self).ok().and_then(|metadata| metadata.modified().ok())
+    }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        vec![self.to_owned()]
+    }
+
+    fn fingerprint(&self) -> Option<Vec<u8>> {
+        if self.is_dir() {
+            return dir_fingerprint(self);
+        }
+        file_fingerprint(self)
+    }
+}
+
+impl Resource for PathBuf {
+    fn name(&self) -> String {
+        self.as_path().name()
+    }
+
+    fn timestamp(&self) -> Option<SystemTime> {
+        self.as_path().timestamp()
+    }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        self.as_path().paths()
+    }
+
+    fn fingerprint(&self) -> Option<Vec<u8>> {
+        self.as_path().fingerprint()
+    }
 }
 
 //TODO: test
@@ -94,6 +304,298 @@ pub fn timestamp<T: AsResource<R>, R: Resource>(iter: impl Iterator<Item=T>) ->
     })
 }
 
+/// Content fingerprint of a file or None if it can not be read
+pub(crate) fn file_fingerprint(path: &Path) -> Option<Vec<u8>> {
+    std::fs::read(path).ok().map(|bytes| Sha256::digest(bytes).to_vec())
+}
+
+/// Fingerprint of all entries of a directory, recursively, or None if it is not a directory
+pub(crate) fn dir_fingerprint(path: &Path) -> Option<Vec<u8>> {
+    if !path.is_dir() {
+        return None;
+    }
+
+    let entries = walkdir::WalkDir::new(path).follow_links(true).min_depth(1).into_iter()
+        .filter_map(|e| e.ok())
+        .map(|e| e.into_path());
+    entries_fingerprint(path, entries)
+}
+
+/// Fingerprint of directory entries combining their sorted paths relative to `root` with content
+/// fingerprints of files among them
+pub(crate) fn entries_fingerprint<I>(root: &Path, entries: I) -> Option<Vec<u8>>
+    where I: Iterator<Item=PathBuf>
+{
+    let mut entries: Vec<PathBuf> = entries.collect();
+    entries.sort();
+
+    let mut hasher = Sha256::new();
+    for path in entries {
+        hasher.update(path.strip_prefix(root).unwrap_or(&path).to_string_lossy().as_bytes());
+        hasher.update([0]);
+        if path.is_file() {
+            hasher.update(file_fingerprint(&path)?);
+        }
+    }
+    Some(hasher.finalize().to_vec())
+}
+
+/// Check if a resource with `timestamp` is up to date with `other` using the rules of
+/// [`mk_from`](trait.Resource.html#method.mk_from): a resource that does not exist is never up to
+/// date and neither is one compared to a resource without timestamp or one forcing rebuilds.
+pub(crate) fn up_to_date<R: Resource>(timestamp: Option<SystemTime>, other: &R) -> bool {
+    match (timestamp, other.timestamp()) {
+        (Some(own), Some(other_time)) => !other.forces_rebuild() && other_time <= own,
+        _ => false,
+    }
+}
+
+/// File name of the path or the whole path if it has none, like a root directory
+pub(crate) fn path_name(path: &Path) -> String {
+    match path.file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => path.display().to_string(),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Check if `output` needs to be built from `src` by timestamps, logging the build if it does
+fn needs_build(output: &dyn Resource, src: &dyn Resource, description: &str) -> bool {
+    let target_time = output.output_timestamp();
+    if target_time.is_none() || src.forces_rebuild() || src.timestamp() > target_time {
+        log!("Building: {} from {}: {}", output.name(), src.name(), description);
+        return true;
+    }
+    false
+}
+
+/// Name and timestamp of the newest of given input `resources` or None if none of them has one.
+///
+/// Useful for logging which of the inputs triggered the rebuild of some output.
+pub fn newest_input(resources: &[&dyn Resource]) -> Option<(String, SystemTime)> {
+    resources.iter().fold(None, |result, res| match (res.timestamp(), &result) {
+        (Some(time), Some((_, newest))) if time <= *newest => result,
+        (Some(time), _) => Some((res.name(), time)),
+        (None, _) => result,
+    })
+}
+
+//-- Parallel build --------------------------------------------------------------------------------
+
+/// Build step of [`build_parallel`](fn.build_parallel.html) building `output` from `input` by
+/// running a function the same way as [`Resource::mk_from`](trait.Resource.html#method.mk_from)
+pub struct BuildStep<'a> {
+    output: &'a dyn Resource,
+    description: &'a str,
+    input: &'a dyn Resource,
+    by: Box<dyn FnOnce() + Send + 'a>,
+}
+
+impl<'a> BuildStep<'a> {
+
+    /// Creates a step building `output` from `input` by running `by` function if needed
+    pub fn new<F>(output: &'a dyn Resource, description: &'a str, input: &'a dyn Resource, by: F)
+        -> Self where F: FnOnce() + Send + 'a
+    {
+        BuildStep { output, description, input, by: Box::new(by) }
+    }
+}
+
+/// Runs functions of the build `steps` that need to be built on up to `jobs` threads returning the
+/// number of steps ran.
+///
+/// Which steps need to be built is decided upfront by the rules of
+/// [`Resource::mk_from`](trait.Resource.html#method.mk_from), so only the functions themselves run
+/// concurrently and need to be `Send`. The number of threads is typically given by
+/// [`Build::num_jobs`](struct.Build.html#method.num_jobs). If any of the functions panics, no
+/// further steps are started and the panic is raised again once the running ones complete.
+pub fn build_parallel(jobs: usize, steps: Vec<BuildStep>) -> usize {
+    let stale: Vec<_> = steps.into_iter()
+        .filter(|step| needs_build(step.output, step.input, step.description))
+        .map(|step| step.by)
+        .collect();
+    let count = stale.len();
+
+    let queue = Mutex::new(stale.into_iter());
+    let panics = Mutex::new(vec![]);
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, count.max(1)) {
+            scope.spawn(|| loop {
+                if !panics.lock().unwrap().is_empty() {
+                    break;
+                }
+                let by = match queue.lock().unwrap().next() {
+                    Some(by) => by,
+                    None => break,
+                };
+                if let Err(panic) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(by)) {
+                    panics.lock().unwrap().push(panic);
+                }
+            });
+        }
+    });
+
+    if let Some(panic) = panics.into_inner().unwrap().into_iter().next() {
+        std::panic::resume_unwind(panic);
+    }
+    count
+}
+
+//-- Always ----------------------------------------------------------------------------------------
+
+/// Input resource that forces rebuild of any output built from it in every build run
+///
+/// Useful for build steps that should always run regardless of timestamps, like regenerating a
+/// version string from `git`. It has neither timestamp nor fingerprint and can be combined with
+/// other inputs in a [`Set`](struct.Set.html) to force rebuild of the whole step.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Always;
+
+impl Resource for Always {
+    fn timestamp(&self) -> Option<SystemTime> {
+        None
+    }
+
+    fn forces_rebuild(&self) -> bool {
+        true
+    }
+}
+
+//-- Deferred --------------------------------------------------------------------------------------
+
+/// Resource whose identity is resolved lazily by a closure on first [`timestamp()`] call
+///
+/// Allows declaring a dependency on a resource that can only be determined after some earlier
+/// build steps ran, for example an output whose location is known only once it is generated. The
+/// resolved resource is kept for subsequent calls while it's timestamp is always queried anew.
+///
+/// [`timestamp()`]: trait.Resource.html#tymethod.timestamp
+///
+pub struct Deferred<R, F> {
+    resolve: F,
+    resource: OnceCell<R>,
+}
+
+impl<R, F> Deferred<R, F> where R: Resource, F: Fn() -> R {
+
+    /// Create a new deferred resource that will be resolved by `resolve` closure
+    pub fn new(resolve: F) -> Self {
+        Deferred { resolve, resource: OnceCell::new() }
+    }
+
+    /// Resolved underlying resource, invoking the closure if not resolved yet
+    pub fn resource(&self) -> &R {
+        self.resource.get_or_init(&self.resolve)
+    }
+}
+
+impl<R, F> Debug for Deferred<R, F> where R: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.resource.get() {
+            Some(resource) => resource.fmt(f),
+            None => f.write_str("Deferred(unresolved)"),
+        }
+    }
+}
+
+impl<R, F> Resource for Deferred<R, F> where R: Resource, F: Fn() -> R {
+    fn timestamp(&self) -> Option<SystemTime> {
+        self.resource().timestamp()
+    }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        self.resource().paths()
+    }
+
+    fn fingerprint(&self) -> Option<Vec<u8>> {
+        self.resource().fingerprint()
+    }
+
+    fn output_timestamp(&self) -> Option<SystemTime> {
+        self.resource().output_timestamp()
+    }
+
+    fn name(&self) -> String {
+        self.resource().name()
+    }
+
+    fn forces_rebuild(&self) -> bool {
+        self.resource().forces_rebuild()
+    }
+}
+
+//-- Virtual ---------------------------------------------------------------------------------------
+
+/// Resource backed by arbitrary state instead of the file system, like an output of a command or
+/// an environment variable
+///
+/// The state is queried by a closure each time the resource's [`timestamp()`] or [`fingerprint()`]
+/// is needed. Created by [`new`](#method.new) it drives [`mk_from()`] by a timestamp, while created
+/// by [`from_fingerprint`](#method.from_fingerprint) it drives [`mk_from_hashed()`] by content.
+///
+/// [`timestamp()`]: trait.Resource.html#tymethod.timestamp
+/// [`fingerprint()`]: trait.Resource.html#method.fingerprint
+/// [`mk_from()`]: trait.Resource.html#method.mk_from
+/// [`mk_from_hashed()`]: trait.Resource.html#method.mk_from_hashed
+///
+pub struct Virtual<'a> {
+    name: String,
+    timestamp: Box<dyn Fn() -> Option<SystemTime> + 'a>,
+    fingerprint: Box<dyn Fn() -> Option<Vec<u8>> + 'a>,
+}
+
+impl<'a> Virtual<'a> {
+
+    /// Create a new virtual resource with timestamp provided by `timestamp` closure
+    pub fn new<F>(timestamp: F) -> Self where F: Fn() -> Option<SystemTime> + 'a {
+        Virtual {
+            name: "Virtual".to_owned(),
+            timestamp: Box::new(timestamp),
+            fingerprint: Box::new(|| None),
+        }
+    }
+
+    /// Create a new virtual resource without timestamp and with fingerprint made of the content
+    /// provided by `content` closure, like output of `git rev-parse HEAD`
+    pub fn from_fingerprint<F>(content: F) -> Self where F: Fn() -> Option<Vec<u8>> + 'a {
+        Virtual {
+            name: "Virtual".to_owned(),
+            timestamp: Box::new(|| None),
+            fingerprint: Box::new(move || content().map(|bytes| Sha256::digest(bytes).to_vec())),
+        }
+    }
+
+    /// Sets the `name` the resource is logged with
+    pub fn named<N: Into<String>>(mut self, name: N) -> Self {
+        self.name = name.into();
+        self
+    }
+}
+
+impl Debug for Virtual<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl Resource for Virtual<'_> {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn timestamp(&self) -> Option<SystemTime> {
+        (self.timestamp)()
+    }
+
+    fn fingerprint(&self) -> Option<Vec<u8>> {
+        (self.fingerprint)()
+    }
+}
+
 //-- Set -------------------------------------------------------------------------------------------
 
 /// Ordered list of owned resources
@@ -106,6 +608,24 @@ pub struct Set<T> {
     items: Vec<T>
 }
 
+impl<T> Set<T> {
+
+    /// Number of resources in the set
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// True if the set contains no resources
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Iterator over references to resources in the set
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+}
+
 impl<R> Add<&R> for Set<R> where R: Clone {
     type Output = Set<R>;
 
@@ -124,6 +644,24 @@ impl<R> Add<R> for Set<R> {
     }
 }
 
+impl<R> Add<&Set<R>> for Set<R> where R: Clone {
+    type Output = Set<R>;
+
+    fn add(mut self, rhs: &Set<R>) -> Self::Output {
+        self.items.extend(rhs.items.iter().cloned());
+        self
+    }
+}
+
+impl<R> Add<Set<R>> for Set<R> {
+    type Output = Set<R>;
+
+    fn add(mut self, rhs: Set<R>) -> Self::Output {
+        self.items.extend(rhs.items);
+        self
+    }
+}
+
 impl<T> AsRef<Set<T>> for Set<T> {
     fn as_ref(&self) -> &Set<T> {
         self
@@ -136,6 +674,21 @@ impl<T> From<Vec<T>> for Set<T> {
     }
 }
 
+impl<T> std::iter::FromIterator<T> for Set<T> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> Self {
+        Set { items: iter.into_iter().collect() }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Set<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
 impl<T> IntoIterator for Set<T> {
     type Item = T;
     type IntoIter = std::vec::IntoIter<Self::Item>;
@@ -147,7 +700,27 @@ impl<T> IntoIterator for Set<T> {
 
 impl<R> Resource for Set<R> where R:Resource {
 
+    fn name(&self) -> String {
+        self.items.name()
+    }
+
+    fn output_timestamp(&self) -> Option<SystemTime> {
+        self.items.output_timestamp()
+    }
+
     fn timestamp(&self) -> Option<SystemTime> {
         self.items.timestamp()
     }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        self.items.paths()
+    }
+
+    fn fingerprint(&self) -> Option<Vec<u8>> {
+        self.items.fingerprint()
+    }
+
+    fn forces_rebuild(&self) -> bool {
+        self.items.forces_rebuild()
+    }
 }
\ No newline at end of file
@@ -0,0 +1,337 @@
+use std::sync::Mutex;
+
+use devbox_build::*;
+use devbox_test_args::args;
+
+mod common;
+use common::directives;
+
+// Serializes tests setting Cargo environment variables read by other tests
+static ENV: Mutex<()> = Mutex::new(());
+
+// directives --------------------------------------------------------------------------------------
+
+#[test]
+fn build_rustc_cfg() {
+    assert_eq!(
+        vec![
+            "cargo:rustc-cfg=foo",
+            "cargo:rustc-check-cfg=cfg(bar, values(\"baz\"))",
+            "cargo:rustc-cfg=bar=\"baz\"",
+            "cargo:rustc-check-cfg=cfg(qux)",
+            "cargo:rustc-cfg=qux",
+            "cargo:rustc-check-cfg=cfg(quux)",
+        ],
+        directives("build_rustc_cfg", || {
+            let build = Build::new();
+            build.rustc_cfg("foo");
+            build.rustc_cfg_declared("bar=\"baz\"");
+            build.rustc_cfg_declared("qux");
+            build.rustc_check_cfg("cfg(quux)");
+        })
+    );
+}
+
+#[test]
+fn build_link() {
+    // Same location in the child process emitting the directives
+    let lib = Dir::new(env!("CARGO_TARGET_TMPDIR")).dir("lib");
+
+    assert_eq!(
+        vec![
+            "cargo:rustc-link-lib=z".to_owned(),
+            "cargo:rustc-link-lib=static=foo".to_owned(),
+            format!("cargo:rustc-link-search={}", lib),
+            format!("cargo:rustc-link-search=native={}", lib),
+            "cargo:warning=first".to_owned(),
+            "cargo:warning=second".to_owned(),
+        ],
+        directives("build_link", || {
+            let build = Build::new();
+            build.link_lib(None, "z");
+            build.link_lib(Some("static"), "foo");
+            build.link_search(None, &lib);
+            build.link_search(Some("native"), &lib);
+            build.warning("first\nsecond");
+        })
+    );
+}
+
+#[test]
+fn build_rerun_if_changed() {
+    // Same location in the child process emitting the directives
+    let root = Dir::new(env!("CARGO_TARGET_TMPDIR")).dir("build_rerun_if_changed").created();
+    let file = root.file("in.txt").created();
+    let dir = root.dir("src").created();
+    let rs = dir.file("a.rs").created();
+
+    let rerun = |path: &dyn AsRef<std::path::Path>| {
+        format!("cargo:rerun-if-changed={}", path.as_ref().display())
+    };
+
+    assert_eq!(
+        vec![
+            rerun(&file),
+            rerun(&dir),
+            rerun(&root.file("build.rs")),
+            rerun(&file),
+            rerun(&rs),
+            rerun(&rs),
+            "cargo:rerun-if-env-changed=FOO_SYS_DIR".to_owned(),
+        ],
+        directives("build_rerun_if_changed", || {
+            let build = Build::new();
+            build.rerun_if_changed(&file);
+            build.rerun_if_changed(&dir);
+            build.rerun_if_changed(root.path().join("build.rs"));
+            build.rerun_if_resource_changed(&Set::from(vec![file.clone(), rs.clone()]));
+            build.rerun_if_resource_changed(&dir.files("*.rs"));
+            build.rerun_if_env_changed("FOO_SYS_DIR");
+        })
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn build_tool_version_cfg_declared() {
+    assert_eq!(
+        vec![
+            "cargo:rustc-check-cfg=cfg(has_gcc_10)",
+            "cargo:rustc-cfg=has_gcc_10",
+            "cargo:rustc-check-cfg=cfg(has_gcc_20)",
+        ],
+        directives("build_tool_version_cfg_declared", || {
+            let gcc = Cmd::new("echo").arg("gcc (GCC) 12.3.0");
+            Build::new().tool_version_cfg(gcc.clone(), "10", "has_gcc_10");
+            Build::new().tool_version_cfg(gcc, "20", "has_gcc_20");
+        })
+    );
+}
+
+#[test]
+fn build_tool_version_cfg_missing_tool() {
+    assert_eq!(
+        vec!["cargo:rustc-check-cfg=cfg(has_missing)"],
+        directives("build_tool_version_cfg_missing_tool", || {
+            let missing = Cmd::new("devbox-missing-tool-3f9a");
+            assert!(!Build::new().tool_version_cfg(missing, "1", "has_missing"));
+        })
+    );
+}
+
+// accessors ---------------------------------------------------------------------------------------
+
+#[test]
+fn build_try_new() {
+    let _env = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let manifest = std::env::var_os("CARGO_MANIFEST_DIR").unwrap();
+
+    std::env::remove_var("OUT_DIR");
+    let err = Build::try_new().unwrap_err();
+    assert_eq!(BuildError::MissingEnvVar("OUT_DIR".to_owned()), err);
+    assert!(err.to_string().contains("OUT_DIR"), "{}", err);
+
+    std::env::set_var("OUT_DIR", std::env::temp_dir());
+    std::env::remove_var("CARGO_MANIFEST_DIR");
+    let err = Build::try_new().unwrap_err();
+    assert_eq!(BuildError::MissingEnvVar("CARGO_MANIFEST_DIR".to_owned()), err);
+
+    std::env::set_var("CARGO_MANIFEST_DIR", manifest);
+    assert!(Build::try_new().is_ok());
+}
+
+#[test]
+fn build_stamp() {
+    let _env = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let temp = tempfile::tempdir().unwrap();
+    std::env::set_var("OUT_DIR", temp.path());
+    let build = Build::new();
+    let input = temp.path().join("input.txt");
+    std::fs::write(&input, "input").unwrap();
+    let input = File::new(&input).unwrap();
+
+    let stamp = build.stamp("step");
+    assert_eq!(temp.path().join(".stamps/step"), stamp.path());
+    assert!(stamp.mk_from("Test", &input, || stamp.touch()));
+    assert!(stamp.exists());
+    assert!(!stamp.mk_from("Test", &input, || panic!("up to date")));
+}
+
+#[test]
+fn build_scratch_dir() {
+    let _env = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let temp = tempfile::tempdir().unwrap();
+    std::env::set_var("OUT_DIR", temp.path());
+
+    let scratch = Build::new().scratch_dir();
+    assert_eq!(temp.path().join(".scratch"), scratch.path());
+    let work = scratch.temp_scoped("npm-");
+    assert_eq!(Some(scratch.path()), work.path().parent());
+}
+
+#[test]
+fn build_profile_dir() {
+    let _env = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let build = Build::new();
+    let target = std::env::temp_dir().join("target");
+    std::env::set_var("TARGET", "x86_64-unknown-linux-gnu");
+
+    std::env::set_var("OUT_DIR", target.join("debug/build/foo-0123456789abcdef/out"));
+    assert_eq!(target.join("debug"), build.profile_dir().path());
+    assert_eq!(target, build.target_dir().path());
+
+    let cross = target.join("x86_64-unknown-linux-gnu/release");
+    std::env::set_var("OUT_DIR", cross.join("build/foo-0123456789abcdef/out"));
+    assert_eq!(cross, build.profile_dir().path());
+    assert_eq!(target, build.target_dir().path());
+
+    std::env::set_var("OUT_DIR", target.join("out"));
+    assert!(std::panic::catch_unwind(|| build.profile_dir()).is_err());
+}
+
+#[test]
+fn build_profile() {
+    let _env = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let build = Build::new();
+
+    std::env::set_var("PROFILE", "debug");
+    assert_eq!(Profile::Debug, build.profile());
+    assert!(!build.is_release_build());
+
+    std::env::set_var("PROFILE", "release");
+    assert_eq!(Profile::Release, build.profile());
+    assert!(build.is_release_build());
+
+    std::env::set_var("PROFILE", "bench-lto");
+    assert_eq!(Profile::Custom("bench-lto".to_owned()), build.profile());
+    assert!(!build.is_release_build());
+}
+
+#[test]
+fn build_features() {
+    let _env = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let build = Build::new();
+    std::env::set_var("CARGO_FEATURE_FOO_BAR", "1");
+    std::env::set_var("CARGO_FEATURE_DEFAULT", "1");
+
+    assert_eq!(vec!["default", "foo-bar"], build.features());
+    assert!(build.has_feature("foo-bar"));
+    assert!(build.has_feature("default"));
+    assert!(!build.has_feature("baz"));
+
+    std::env::remove_var("CARGO_FEATURE_FOO_BAR");
+    std::env::remove_var("CARGO_FEATURE_DEFAULT");
+}
+
+#[test]
+fn build_target() {
+    let _env = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let build = Build::new();
+    std::env::set_var("CARGO_CFG_TARGET_ARCH", "aarch64");
+    std::env::set_var("CARGO_CFG_TARGET_OS", "linux");
+    std::env::set_var("CARGO_CFG_TARGET_FAMILY", "unix");
+    std::env::set_var("CARGO_CFG_TARGET_ENV", "");
+    std::env::set_var("CARGO_CFG_TARGET_POINTER_WIDTH", "64");
+
+    assert_eq!("aarch64", build.target_arch());
+    assert_eq!("linux", build.target_os());
+    assert_eq!("unix", build.target_family());
+    assert_eq!("", build.target_env());
+    assert_eq!(64, build.target_pointer_width());
+    assert_eq!(Some("linux".to_owned()), build.cfg("target-os"));
+    assert_eq!(None, build.cfg("target_vendor_none"));
+
+    std::env::set_var("HOST", "x86_64-unknown-linux-gnu");
+    std::env::set_var("TARGET", "x86_64-unknown-linux-gnu");
+    assert!(!build.is_cross_compiling());
+    std::env::set_var("TARGET", "aarch64-unknown-linux-gnu");
+    assert!(build.is_cross_compiling());
+}
+
+#[test]
+fn build_prefixed_env_var() {
+    // Variables are looked up right after the prefix, not separated by another underscore
+    let _env = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let build = Build::new();
+    std::env::set_var("CARGO_FEATURE__DEVBOX_UNDERSCORED", "1");
+    std::env::set_var("CARGO_CFG__DEVBOX_UNDERSCORED", "yes");
+    assert!(!build.has_feature("devbox-underscored"));
+    assert_eq!(None, build.cfg("devbox_underscored"));
+
+    std::env::set_var("CARGO_FEATURE_DEVBOX_UNDERSCORED", "1");
+    std::env::set_var("CARGO_CFG_DEVBOX_UNDERSCORED", "no");
+    assert!(build.has_feature("devbox-underscored"));
+    assert_eq!(Some("no".to_owned()), build.cfg("devbox-underscored"));
+
+    for var in ["CARGO_FEATURE_", "CARGO_FEATURE__", "CARGO_CFG_", "CARGO_CFG__"] {
+        std::env::remove_var(format!("{}DEVBOX_UNDERSCORED", var));
+    }
+}
+
+// tool version ------------------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[args(
+    equal: "v18.2.0", "18.2";
+    above: "node v18.12.1 (LTS)", "18.2";
+    major: "tool 19", "18.2.1";
+    below: "v16.20.0", "18" ! "lower than required";
+    minor: "version 1.9.9", "1.10" ! "lower than required";
+    nover: "unknown", "1" ! "could not be determined";
+)]
+#[test]
+fn build_require_tool_version(output: &str, min: &str) {
+    Build::new().require_tool_version(Cmd::new("echo").arg(output), min);
+}
+
+#[test]
+#[should_panic(expected = "version could not be determined")]
+fn build_require_tool_version_missing_tool() {
+    Build::new().require_tool_version(Cmd::new("devbox-missing-tool-3f9a"), "1");
+}
+
+#[cfg(unix)]
+#[args(
+    above: "gcc (GCC) 12.3.0", true;
+    below: "gcc (GCC) 9.4.0", false;
+    nover: "gcc", false;
+)]
+#[test]
+fn build_tool_version_cfg(output: &str, expected: bool) {
+    let gcc = Cmd::new("echo").arg(output);
+    assert_eq!(expected, Build::new().tool_version_cfg(gcc, "10.1", "has_gcc_10"));
+}
+
+// generate_mod_tree -------------------------------------------------------------------------------
+
+#[test]
+fn build_generate_mod_tree() {
+    let temp = tempfile::tempdir().unwrap();
+    let src = Dir::new(temp.path()).dir("src");
+    src.file("lib.rs").created();
+    src.file("my-proto.rs").created();
+    src.file("2d.rs").created();
+    src.file("readme.txt").created();
+    src.dir("nested").file("type.rs").created();
+    src.dir("nested").dir("deep").file("leaf.rs").created();
+
+    let out = Dir::new(temp.path()).file("out/mods.rs");
+    Build::new().generate_mod_tree(&src, &out, "**/*.rs");
+
+    let path = |p: &str| format!("{:?}", src.path().join(p).display().to_string());
+    assert_eq!(
+        std::fs::read_to_string(&out).unwrap(),
+        format!(concat!(
+            "#[path = {}]\npub mod _2d;\n",
+            "#[path = {}]\npub mod lib;\n",
+            "#[path = {}]\npub mod my_proto;\n",
+            "pub mod nested {{\n",
+            "    #[path = {}]\n    pub mod type_;\n",
+            "    pub mod deep {{\n",
+            "        #[path = {}]\n        pub mod leaf;\n",
+            "    }}\n",
+            "}}\n",
+        ), path("2d.rs"), path("lib.rs"), path("my-proto.rs"), path("nested/type.rs"),
+            path("nested/deep/leaf.rs"))
+    );
+}
@@ -0,0 +1,519 @@
+use std::ffi::OsStr;
+use std::path::Path;
+use std::time::Duration;
+
+use devbox_build::*;
+
+// program -----------------------------------------------------------------------------------------
+
+#[test]
+fn cmd_program() {
+    let clang = Cmd::new("clang").arg("-O2").args(["-c", "foo.c"]).env("FOO", "bar");
+    let gcc = clang.clone().program("gcc");
+
+    let command = gcc.command();
+    assert_eq!("gcc", command.get_program());
+    assert_eq!(vec!["-O2", "-c", "foo.c"], command.get_args().collect::<Vec<_>>());
+    assert_eq!(vec![(OsStr::new("FOO"), Some(OsStr::new("bar")))], command.get_envs().collect::<Vec<_>>());
+    assert_eq!("clang", clang.command().get_program());
+}
+
+// arg_if ------------------------------------------------------------------------------------------
+
+#[test]
+fn cmd_arg_if() {
+    let cmd = Cmd::new("npm").arg("run").arg_if(true, "--prod").arg_if(false, "--dev").arg("build");
+    let cmd = cmd.args_if(false, ["-v", "-v"]).args_if(true, ["--", "x"]);
+    assert_eq!(vec!["run", "--prod", "build", "--", "x"], cmd.command().get_args().collect::<Vec<_>>());
+}
+
+// display -----------------------------------------------------------------------------------------
+
+#[test]
+fn cmd_display() {
+    let npm = Cmd::new("npm").arg("--prefix").arg("/path").arg("install").env("FOO", "bar");
+    assert_eq!("FOO=bar npm --prefix /path install", npm.to_string());
+
+    let cmd = Cmd::new("my tool").args(["a b", "", "--x=1"]).env("B", "2").env("A", "x y");
+    #[cfg(unix)]
+    assert_eq!("A='x y' B=2 'my tool' 'a b' '' --x=1", cmd.to_string());
+    #[cfg(windows)]
+    assert_eq!(r#"A="x y" B=2 "my tool" "a b" "" --x=1"#, cmd.to_string());
+
+    #[cfg(unix)]
+    assert_eq!(r#"echo 'it'\''s'"#, Cmd::new("echo").arg("it's").to_string());
+}
+
+// resolve -----------------------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[test]
+fn cmd_resolve() {
+    let sh = Cmd::new("sh").resolve().unwrap();
+    assert!(sh.is_absolute(), "{:?}", sh);
+    assert!(sh.ends_with("sh"), "{:?}", sh);
+    assert!(Cmd::new("sh").exists());
+
+    assert_eq!(None, Cmd::new("devbox-no-such-program").resolve());
+    assert!(!Cmd::new("devbox-no-such-program").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_resolve_path() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let bin = Dir::new(temp.path()).dir("bin").created();
+    let tool = bin.file("devbox-tool").created();
+    let plain = bin.file("devbox-plain").created();
+    std::fs::set_permissions(tool.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = std::env::join_paths([temp.path().join("none"), bin.path().to_owned()]).unwrap();
+    assert_eq!(Some(tool.path().to_owned()), Cmd::new("devbox-tool").env("PATH", &path).resolve());
+    assert_eq!(None, Cmd::new("devbox-plain").env("PATH", &path).resolve());
+    assert_eq!(None, Cmd::new("devbox-tool").resolve());
+
+    assert_eq!(Some(tool.path().to_owned()), Cmd::new(tool.path()).resolve());
+    assert_eq!(None, Cmd::new(plain.path()).resolve());
+    let relative = Cmd::new("bin/devbox-tool").current_dir(temp.path());
+    assert_eq!(Some(tool.path().to_owned()), relative.resolve());
+}
+
+#[cfg(windows)]
+#[test]
+fn cmd_resolve_pathext() {
+    let cmd = Cmd::new("cmd").resolve().unwrap();
+    assert!(cmd.to_string_lossy().to_lowercase().ends_with("cmd.exe"), "{:?}", cmd);
+    assert!(Cmd::new("cmd.exe").exists());
+    assert!(Cmd::new("CMD.EXE").exists());
+}
+
+// env ---------------------------------------------------------------------------------------------
+
+#[test]
+fn cmd_env_remove() {
+    let base = Cmd::new("env").env("FOO", "foo").env("BAR", "bar");
+    let cmd = base.clone().env_remove("FOO").env_remove("HOME");
+
+    let command = cmd.command();
+    let mut envs = command.get_envs().collect::<Vec<_>>();
+    envs.sort();
+    assert_eq!(vec![
+        (OsStr::new("BAR"), Some(OsStr::new("bar"))),
+        (OsStr::new("FOO"), None),
+        (OsStr::new("HOME"), None),
+    ], envs);
+    assert_eq!(2, base.command().get_envs().count());
+
+    let cmd = cmd.env("HOME", "/home");
+    assert!(cmd.command().get_envs().any(|env| env == (OsStr::new("HOME"), Some(OsStr::new("/home")))));
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_env_clear() {
+    let cmd = Cmd::new("/usr/bin/env").env("FOO", "foo").env_remove("BAR").env_clear().env("BAZ", "baz");
+    assert_eq!("BAZ=baz", cmd.stdout());
+
+    std::env::set_var("DEVBOX_TEST_ENV_REMOVE", "1");
+    let output = Cmd::new("env").env_remove("DEVBOX_TEST_ENV_REMOVE").stdout();
+    assert!(!output.contains("DEVBOX_TEST_ENV_REMOVE"), "{}", output);
+}
+
+// path_prepend ------------------------------------------------------------------------------------
+
+#[test]
+fn cmd_path_prepend() {
+    let temp = tempfile::tempdir().unwrap();
+    let bin = Dir::new(temp.path()).dir("node_modules/.bin");
+    let tools = temp.path().join("tools");
+
+    let cmd = Cmd::new("npm").env("PATH", "/usr/bin").path_prepend(&tools).path_prepend(&bin);
+    let path = std::env::join_paths([bin.path(), &tools, Path::new("/usr/bin")]).unwrap();
+    assert!(cmd.command().get_envs().any(|env| env == (OsStr::new("PATH"), Some(path.as_os_str()))));
+
+    let cmd = Cmd::new("npm").env_clear().path_prepend(&bin);
+    assert!(cmd.command().get_envs().any(|env| env == (OsStr::new("PATH"), Some(bin.path().as_os_str()))));
+
+    let cmd = Cmd::new("npm").path_prepend(&bin);
+    let path = cmd.command().get_envs().find(|env| env.0 == "PATH").unwrap().1.unwrap().to_owned();
+    let paths = std::env::split_paths(&path).collect::<Vec<_>>();
+    assert_eq!(bin.path(), paths[0]);
+    assert_eq!(std::env::split_paths(&std::env::var_os("PATH").unwrap()).collect::<Vec<_>>(), paths[1..]);
+
+    assert_eq!(0, Cmd::new("npm").command().get_envs().count());
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_path_prepend_run() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let bin = Dir::new(temp.path()).dir("bin").created();
+    let tool = bin.file("devbox-local-tool").created();
+    std::fs::write(tool.path(), "#!/bin/sh\necho local\n").unwrap();
+    std::fs::set_permissions(tool.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let cmd = Cmd::new("sh").arg("-c").arg("devbox-local-tool").path_prepend(&bin);
+    assert_eq!("local", cmd.stdout());
+    assert_eq!(Some(tool.path().to_owned()), Cmd::new("devbox-local-tool").path_prepend(&bin).resolve());
+}
+
+// current_dir -------------------------------------------------------------------------------------
+
+#[test]
+fn cmd_current_dir() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = Dir::new(temp.path()).dir("work").created();
+    let base = Cmd::new("ls").current_dir(&dir);
+    let other = base.clone().current_dir(temp.path());
+
+    assert_eq!(Some(dir.path()), base.command().get_current_dir());
+    assert_eq!(Some(temp.path()), other.command().get_current_dir());
+    assert_eq!(None, Cmd::new("ls").command().get_current_dir());
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_current_dir_run() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = Dir::new(temp.path()).dir("work").created();
+    dir.file("marker.txt").create();
+
+    let output = Cmd::new("ls").current_dir(&dir).output();
+    assert_eq!("marker.txt\n", String::from_utf8_lossy(&output.stdout));
+}
+
+// stdout ------------------------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[test]
+fn cmd_stdout() {
+    assert_eq!("out", Cmd::new("sh").arg("-c").arg("echo '  out  '; echo err >&2").stdout());
+    assert_eq!("", Cmd::new("true").stdout());
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_stdout_result() {
+    let err = Cmd::new("sh").arg("-c").arg("echo out; echo bad thing >&2; exit 3").stdout_result();
+    let err = err.unwrap_err().to_string();
+    assert!(err.contains("exit status: 3"), "{}", err);
+    assert!(err.ends_with("stderr: bad thing"), "{}", err);
+
+    assert!(Cmd::new("devbox-no-such-program").stdout_result().is_err());
+}
+
+#[cfg(unix)]
+#[test]
+#[should_panic(expected = "stderr: bad thing")]
+fn cmd_stdout_failure() {
+    Cmd::new("sh").arg("-c").arg("echo bad thing >&2; exit 1").stdout();
+}
+
+// output_result -----------------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[test]
+fn cmd_output_result() {
+    let output = Cmd::new("sh").arg("-c").arg("echo out").output_result().unwrap();
+    assert_eq!("out\n", String::from_utf8_lossy(&output.stdout));
+
+    let err = Cmd::new("sh").args(["-c", "echo bad thing >&2; exit 3"]).output_result().unwrap_err();
+    assert_eq!(Some(3), err.code());
+    assert_eq!("bad thing\n", err.stderr());
+    assert_eq!("exit status: 3, stderr: bad thing", err.to_string());
+    match err {
+        CmdError::Exit { program, args, .. } => {
+            assert_eq!("sh", program);
+            assert_eq!(vec!["-c", "echo bad thing >&2; exit 3"], args);
+        }
+        err => panic!("unexpected {:?}", err),
+    }
+
+    let err = Cmd::new("devbox-no-such-program").output_result().unwrap_err();
+    assert!(matches!(err, CmdError::Spawn { .. }), "{:?}", err);
+    assert_eq!(None, err.code());
+    assert_eq!("", err.stderr());
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_checked_output() {
+    let output = Cmd::new("echo").arg("out").checked_output();
+    assert_eq!("out\n", String::from_utf8_lossy(&output.stdout));
+}
+
+#[cfg(unix)]
+#[test]
+#[should_panic(expected = "failed: exit status: 1, stderr: bad thing")]
+fn cmd_checked_output_failure() {
+    Cmd::new("sh").arg("-c").arg("echo bad thing >&2; exit 1").checked_output();
+}
+
+// run ---------------------------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[test]
+#[should_panic(expected = "failed: exit status: 2, stderr: ...\nline 6\nline 7")]
+fn cmd_run_failure_stderr_tail() {
+    Cmd::new("sh").arg("-c").arg("for i in $(seq 1 25); do echo line $i >&2; done; exit 2").run();
+}
+
+#[cfg(unix)]
+#[test]
+#[should_panic(expected = "exit status: 1, stderr: bad thing")]
+fn cmd_run_failure_stderr() {
+    Cmd::new("sh").arg("-c").arg("echo bad thing >&2; exit 1").run();
+}
+
+#[cfg(unix)]
+#[test]
+#[should_panic(expected = "No such file")]
+fn cmd_run_failure_spawn() {
+    Cmd::new("devbox-no-such-program").run();
+}
+
+// expect_status -----------------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[test]
+fn cmd_expect_status() {
+    let differ = Cmd::new("sh").arg("-c").arg("echo differ; exit 1").expect_status(1);
+    differ.run();
+    assert_eq!("differ", differ.stdout());
+    assert_eq!(Some(1), differ.checked_output().status.code());
+
+    let err = Cmd::new("true").expect_status(1).stdout_result().unwrap_err().to_string();
+    assert_eq!("exit status: 0 (expected 1), stderr: ", err);
+
+    let err = Cmd::new("sh").args(["-c", "echo bad >&2; exit 2"]).expect_status(1).output_result();
+    let err = err.unwrap_err();
+    assert_eq!(Some(2), err.code());
+    assert_eq!("exit status: 2 (expected 1), stderr: bad", err.to_string());
+
+    let pipeline = Cmd::new("sh").arg("-c").arg("echo a; exit 1").expect_status(1).pipe_to(Cmd::new("cat"));
+    assert_eq!("a\n", String::from_utf8_lossy(&pipeline.output().stdout));
+    let last = Cmd::new("sh").arg("-c").arg("cat >/dev/null; exit 1").expect_status(1);
+    pipeline.pipe_to(last).run();
+}
+
+#[cfg(unix)]
+#[test]
+#[should_panic(expected = "failed: exit status: 0 (expected 1), stderr: ")]
+fn cmd_expect_status_failure() {
+    Cmd::new("true").expect_status(1).run();
+}
+
+// stdin_bytes -------------------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[test]
+fn cmd_stdin_bytes() {
+    let cmd = Cmd::new("tr").args(["a-z", "A-Z"]).stdin_bytes(b"hello\n".to_vec());
+    assert_eq!("HELLO", cmd.stdout());
+    assert_eq!("HELLO\n", String::from_utf8_lossy(&cmd.output().stdout));
+    assert!(cmd.run_result().unwrap().success());
+    cmd.run();
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_stdin_bytes_large() {
+    let data = "0123456789abcdef".repeat(64 * 1024).into_bytes();
+    let output = Cmd::new("cat").stdin_bytes(data.clone()).output();
+    assert!(output.status.success());
+    assert_eq!(data, output.stdout);
+
+    assert_eq!(data.len().to_string(), Cmd::new("wc").arg("-c").stdin_bytes(data).stdout());
+}
+
+// spawn -------------------------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[test]
+fn cmd_spawn() {
+    let start = std::time::Instant::now();
+    let first = Cmd::new("sleep").arg("0.5").spawn().unwrap();
+    let second = Cmd::new("sh").arg("-c").arg("sleep 0.5; exit 3").spawn_checked();
+
+    let codes = vec![first, second].into_iter().map(|mut child| child.wait().unwrap().code());
+    assert_eq!(vec![Some(0), Some(3)], codes.collect::<Vec<_>>());
+    assert!(start.elapsed() < Duration::from_millis(1000), "{:?}", start.elapsed());
+
+    let check = Cmd::new("sh").arg("-c").arg("test \"$(cat)\" = input").stdin_bytes(b"input".to_vec());
+    assert!(check.spawn_checked().wait().unwrap().success());
+
+    assert!(Cmd::new("devbox-no-such-program").spawn().is_err());
+}
+
+#[cfg(unix)]
+#[test]
+#[should_panic(expected = "Command executon '\"devbox-no-such-program\" [] {}' failed")]
+fn cmd_spawn_failure() {
+    Cmd::new("devbox-no-such-program").spawn_checked().wait().unwrap();
+}
+
+// pipe_to -----------------------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[test]
+fn cmd_pipe_to() {
+    let pipeline = Cmd::new("printf").arg("b\\na\\nc\\n").pipe_to(Cmd::new("sort"));
+    assert_eq!("a\nb\nc\n", String::from_utf8_lossy(&pipeline.output().stdout));
+
+    let pipeline = pipeline.pipe_to(Cmd::new("head").arg("-n1"));
+    assert_eq!("a\n", String::from_utf8_lossy(&pipeline.output().stdout));
+    assert!(pipeline.run_result().unwrap().success());
+    pipeline.run();
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_pipe_to_stdin_bytes() {
+    let data = "line\n".repeat(100_000).into_bytes();
+    let pipeline = Cmd::new("cat").stdin_bytes(data).pipe_to(Cmd::new("wc").arg("-l"));
+    assert_eq!("100000", String::from_utf8_lossy(&pipeline.output().stdout).trim());
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_pipe_to_failure() {
+    let pipeline = Cmd::new("false").pipe_to(Cmd::new("cat"));
+    let err = pipeline.output_result().unwrap_err().to_string();
+    assert!(err.starts_with("stage 1 '\"false\" []': exit status: 1"), "{}", err);
+
+    let pipeline = Cmd::new("echo").pipe_to(Cmd::new("devbox-no-such-program"));
+    let err = pipeline.run_result().unwrap_err().to_string();
+    assert!(err.starts_with("stage 2 '\"devbox-no-such-program\" []'"), "{}", err);
+
+    let pipeline = Cmd::new("echo").pipe_to(Cmd::new("sh").arg("-c").arg("exit 2"));
+    assert_eq!(Some(2), pipeline.run_result().unwrap().code());
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_pipe_to_spawn_failure_reaps_stages() {
+    // Uniquely named `sleep` makes the first stage identifiable among processes of the test binary
+    let temp = tempfile::tempdir().unwrap();
+    let sleep = temp.path().join("devbox-stage1");
+    std::os::unix::fs::symlink(Cmd::new("sleep").resolve().unwrap(), &sleep).unwrap();
+
+    let start = std::time::Instant::now();
+    let pipeline = Cmd::new(&sleep).arg("5").pipe_to(Cmd::new("devbox-no-such-program"));
+    let err = pipeline.run_result().unwrap_err().to_string();
+    assert!(err.starts_with("stage 2 '\"devbox-no-such-program\" []'"), "{}", err);
+    assert!(start.elapsed() < Duration::from_secs(5), "{:?}", start.elapsed());
+
+    // Neither running nor zombie child processes are left behind
+    let parent = std::process::id().to_string();
+    let stages = std::fs::read_dir("/proc").unwrap()
+        .filter_map(|entry| std::fs::read_to_string(entry.unwrap().path().join("stat")).ok())
+        .filter(|stat| stat.contains("(devbox-stage1)"))
+        .filter(|stat| stat.rsplit(')').next().unwrap().split_whitespace().nth(1) == Some(&parent))
+        .count();
+    assert_eq!(0, stages);
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_pipe_to_timeout() {
+    let start = std::time::Instant::now();
+    let pipeline = Cmd::new("sleep").arg("5").timeout(Duration::from_millis(100))
+        .pipe_to(Cmd::new("cat"));
+    let err = pipeline.output_result().unwrap_err().to_string();
+    assert!(err.starts_with("stage 1 '\"sleep\" [\"5\"]': timed out after 100ms"), "{}", err);
+    assert!(start.elapsed() < Duration::from_secs(5), "{:?}", start.elapsed());
+
+    let cat = Cmd::new("cat").timeout(Duration::from_secs(10));
+    let pipeline = Cmd::new("echo").arg("a").pipe_to(cat);
+    assert_eq!("a\n", String::from_utf8_lossy(&pipeline.output().stdout));
+}
+
+#[cfg(unix)]
+#[test]
+#[should_panic(expected = "Pipeline executon failed: stage 3 '\"false\" []': exit status: 1")]
+fn cmd_pipe_to_run_failure() {
+    Cmd::new("echo").pipe_to(Cmd::new("cat")).pipe_to(Cmd::new("false")).run();
+}
+
+// timeout -----------------------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[test]
+fn cmd_timeout() {
+    let start = std::time::Instant::now();
+    let err = Cmd::new("sleep").arg("5").timeout(Duration::from_millis(100)).run_result().unwrap_err();
+    assert_eq!(std::io::ErrorKind::TimedOut, err.kind());
+    assert!(start.elapsed() < Duration::from_secs(5), "{:?}", start.elapsed());
+
+    let cmd = Cmd::new("sh").arg("-c").arg("exit 3").timeout(Duration::from_secs(10));
+    assert_eq!(Some(3), cmd.run_result().unwrap().code());
+    assert!(Cmd::new("true").timeout(Duration::from_secs(10)).run_result().unwrap().success());
+}
+
+#[cfg(unix)]
+#[test]
+#[should_panic(expected = "timed out after 100ms")]
+fn cmd_timeout_run() {
+    Cmd::new("sleep").arg("5").timeout(Duration::from_millis(100)).run();
+}
+
+// timed -------------------------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[test]
+fn cmd_timed_run() {
+    let elapsed = Cmd::new("sleep").arg("0.1").timed_run();
+    assert!(elapsed >= Duration::from_millis(100), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(10), "{:?}", elapsed);
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_timed_output() {
+    let (output, elapsed) = Cmd::new("sh").arg("-c").arg("sleep 0.1; echo done").timed_output();
+    assert_eq!("done\n", String::from_utf8_lossy(&output.stdout));
+    assert!(elapsed >= Duration::from_millis(100), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(10), "{:?}", elapsed);
+}
+
+#[cfg(unix)]
+#[test]
+#[should_panic(expected = "failed: exit status: 1")]
+fn cmd_timed_run_failure() {
+    Cmd::new("false").timed_run();
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_timed() {
+    let cmd = Cmd::new("sh").arg("-c").arg("echo done").timed(true);
+    cmd.run();
+    assert_eq!("done", cmd.stdout());
+    assert_eq!("done\n", String::from_utf8_lossy(&cmd.output().stdout));
+    assert!(cmd.clone().timed(false).output_result().is_ok());
+}
+
+// stderr_to_file ----------------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[test]
+fn cmd_stderr_to_file() {
+    let temp = tempfile::tempdir().unwrap();
+    let log = Dir::new(temp.path()).file("logs/stderr.log");
+
+    let cmd = Cmd::new("sh").arg("-c").arg("echo out; echo err >&2").stderr_to_file(&log);
+    let output = cmd.output();
+
+    assert!(output.status.success());
+    assert_eq!("", String::from_utf8_lossy(&output.stdout));
+    assert_eq!("", String::from_utf8_lossy(&output.stderr));
+    assert_eq!("err\n", std::fs::read_to_string(log.path()).unwrap());
+
+    cmd.run();
+    assert_eq!("err\n", std::fs::read_to_string(log.path()).unwrap());
+}
@@ -0,0 +1,25 @@
+/// Runs `emit` in a child process running only given `test` of this test binary and returns the
+/// `cargo:` directives it printed.
+#[allow(dead_code)]
+pub fn directives<F: FnOnce()>(test: &str, emit: F) -> Vec<String> {
+    printed(test, emit).iter()
+        .filter_map(|line| line.find("cargo:").map(|i| line[i..].to_owned()))
+        .collect()
+}
+
+/// Runs `emit` in a child process running only given `test` of this test binary and returns all
+/// the lines it printed to standard output, including those of the test harness.
+pub fn printed<F: FnOnce()>(test: &str, emit: F) -> Vec<String> {
+    if std::env::var("DEVBOX_TEST_EMIT").is_ok() {
+        emit();
+        std::process::exit(0);
+    }
+
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args([test, "--exact", "--nocapture", "--test-threads=1"])
+        .env("DEVBOX_TEST_EMIT", "1")
+        .output()
+        .unwrap();
+
+    String::from_utf8_lossy(&output.stdout).lines().map(str::to_owned).collect()
+}
@@ -1,8 +1,15 @@
-use std::path::{PathBuf};
+#![allow(clippy::bool_assert_comparison)]
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use devbox_build::*;
 use devbox_test_args::test_args;
 
+mod common;
+use common::directives;
+
 
 fn dir_fix() -> (tempfile::TempDir, Dir, Dir) {
     let temp = tempfile::tempdir().unwrap();
@@ -29,6 +36,31 @@ fn dir_accept_path_like() {
     Dir::new(a_pth_buf.clone()).dir(r_pth_buf.clone()).file(r_pth_buf.clone());
 }
 
+// normalize ---------------------------------------------------------------------------------------
+
+#[test_args(
+    absolute:           "/foo/bar", Some("/foo/bar");
+    folded:             "/foo/./bar/../baz", Some("/foo/baz");
+    relative:           "foo/../bar", Some("bar");
+    relative_escape:    "foo/../..", None;
+    root:               "/", None;
+    root_escape:        "/foo/../..", None;
+    root_reset:         "foo/..", None;
+    empty:              "", None;
+    drive:              #[cfg(windows)] r"C:\foo\..\bar", Some(r"C:\bar");
+    drive_escape:       #[cfg(windows)] r"C:\foo\..\..", None;
+    drive_relative:     #[cfg(windows)] r"C:foo\bar", Some(r"C:foo\bar");
+    drive_rel_escape:   #[cfg(windows)] r"C:..\foo", None;
+    drive_rel_deep:     #[cfg(windows)] r"C:..\..\a\b\c", None;
+    unc:                #[cfg(windows)] r"\\server\share\foo\..\bar", Some(r"\\server\share\bar");
+    unc_escape:         #[cfg(windows)] r"\\server\share\..", None;
+    verbatim:           #[cfg(windows)] r"\\?\C:\foo", Some(r"\\?\C:\foo");
+    verbatim_unc:       #[cfg(windows)] r"\\?\UNC\server\share\foo", Some(r"\\?\UNC\server\share\foo");
+)]
+fn dir_normalize(path: &str, expected: Option<&str>) {
+    assert_eq!(expected.map(PathBuf::from), normalize(path));
+}
+
 // new ---------------------------------------------------------------------------------------------
 
 #[test_args(
@@ -45,6 +77,54 @@ fn dir_new(new:_, path:_) {
     assert_eq!(PathBuf::from("/foo/bar/baz"), new(path).path());
 }
 
+#[test_args(
+    path: |p| Dir::from(Path::new(p));
+    buff: |p| Dir::from(PathBuf::from(p));
+    strr: |p| Dir::from(p);
+)]
+#[test_args(
+    simple: "/foo/bar/baz";
+    resolv: "/foo/../foo/bar/baz/x/y/../..";
+    nonabs_simple: "foo/bar/baz" ! "is not absolute";
+    nonabs_resolv: "/foo/../../x/foo/bar/baz" ! "is not absolute"
+)]
+fn dir_from(from:_, path:_) {
+    assert_eq!(PathBuf::from("/foo/bar/baz"), from(path).path());
+}
+
+// exists ------------------------------------------------------------------------------------------
+
+#[test]
+fn dir_exists() {
+    let (_, root, dir) = dir_fix();
+    assert!(!dir.exists());
+
+    dir.create();
+    assert!(dir.exists());
+    assert!(root.dir("link").linked_to(&dir).exists());
+    assert!(!dir.dir("file.txt").exists());
+    dir.file("file.txt").create();
+    assert!(!dir.dir("file.txt").exists());
+}
+
+// is_empty ----------------------------------------------------------------------------------------
+
+#[test]
+fn dir_is_empty() {
+    let (_, _, dir) = dir_fix();
+    assert_eq!(true, dir.is_empty());
+    assert_eq!(0, dir.entry_count());
+
+    dir.create();
+    assert_eq!(true, dir.is_empty());
+    assert_eq!(0, dir.entry_count());
+
+    dir.file("a.txt").create();
+    dir.file("nested/b.txt").create();
+    assert_eq!(false, dir.is_empty());
+    assert_eq!(2, dir.entry_count());
+}
+
 // dir ---------------------------------------------------------------------------------------------
 
 #[test_args(
@@ -205,6 +285,67 @@ fn dir_timestamp() {
     assert_eq!(std::fs::metadata(dir.path()).unwrap().modified().unwrap(), dir.timestamp().unwrap());
 }
 
+// temp --------------------------------------------------------------------------------------------
+
+#[test]
+fn dir_temp() {
+    let (_, _, dir) = dir_fix();
+    let first = dir.temp("step-");
+    let second = dir.temp_result("step-").unwrap();
+
+    assert_ne!(first, second);
+    assert!(first.exists() && second.exists());
+    assert!(first.name().starts_with(&format!("step-{}-", std::process::id())), "{}", first);
+    assert_eq!(Some(dir.path()), first.path().parent());
+
+    //skipping names left behind
+    let count: usize = second.name().rsplit('-').next().unwrap().parse().unwrap();
+    for next in count + 1..count + 50 {
+        dir.file(format!("step-{}-{}/left.txt", std::process::id(), next)).create();
+    }
+    let third = dir.temp("step-");
+    assert!(third.is_empty(), "{}", third);
+}
+
+#[test]
+fn dir_temp_scoped() {
+    let (_, _, dir) = dir_fix();
+
+    let scoped = dir.temp_scoped("scoped-");
+    let path = scoped.path().to_owned();
+    scoped.file("nested/file.txt").create();
+    assert!(path.exists());
+    drop(scoped);
+    assert!(!path.exists());
+
+    let kept = dir.temp_scoped("kept-").keep();
+    assert!(kept.exists());
+}
+
+#[test]
+#[should_panic(expected = "Creating temp dir in")]
+fn dir_temp_failure() {
+    let (_, root, _) = dir_fix();
+    root.file("file").create();
+    root.dir("file").temp("step-");
+}
+
+// is_newer_than -----------------------------------------------------------------------------------
+
+#[test]
+fn dir_is_newer_than() {
+    let (_, root, dir) = dir_fix();
+    let src = root.file("src.txt").created();
+    assert_eq!(false, dir.is_newer_than(&src));
+
+    dir.file("out.txt").create();
+    assert_eq!(true, dir.is_newer_than(&src));
+    let later = SystemTime::now() + Duration::from_secs(100);
+    filetime::set_file_mtime(src.path(), filetime::FileTime::from_system_time(later)).unwrap();
+    assert_eq!(false, dir.is_newer_than(&src));
+    assert_eq!(false, dir.is_newer_than(&root.dir("missing")));
+}
+
 // touch -------------------------------------------------------------------------------------------
 
 #[test_args(
@@ -228,6 +369,116 @@ fn dir_touch(touch:_) {
     assert_eq!(true, before < std::fs::metadata(dir.path()).unwrap().modified().unwrap());
 }
 
+// copy_to -----------------------------------------------------------------------------------------
+
+#[test_args(
+    safe: |dir:&Dir,dest| { dir.copy_to_result(dest).unwrap(); };
+    easy: |dir:&Dir,dest| { dir.copy_to(dest); };
+    bild: |dir:&Dir,dest| { dir.clone().copied_to(dest); }
+)]
+fn dir_copy_to(copy_to:_) {
+    let (_, root, dir) = dir_fix();
+    dir.file("top.txt").create().write_all(b"top").unwrap();
+    dir.file("sub/deep/deep.txt").create().write_all(b"deep").unwrap();
+    dir.dir("empty").create();
+    dir.file("link.txt").link_to(&dir.file("top.txt"));
+    dir.dir("sub/link").link_to(&dir.dir("sub/deep"));
+
+    let dest = root.dir("copy/dest");
+    dest.file("other.txt").create();
+    dest.file("top.txt").create().write_all(b"old").unwrap();
+    copy_to(&dir, &dest);
+    copy_to(&dir, &dest);
+
+    let read = |path| std::fs::read_to_string(dest.file(path).path()).unwrap();
+    assert_eq!("top", read("top.txt"));
+    assert_eq!("deep", read("sub/deep/deep.txt"));
+    assert!(dest.dir("empty").path().is_dir());
+    assert!(dest.file("other.txt").path().exists());
+    let link = |path| std::fs::read_link(dest.path().join(path)).unwrap();
+    assert_eq!(dir.file("top.txt").path(), link("link.txt"));
+    assert_eq!(dir.dir("sub/deep").path(), link("sub/link"));
+}
+
+#[test_args(
+    safe: |dir:&Dir,dest| { dir.copy_to_result(dest).expect("Copy"); } ! "Copy";
+    easy: |dir:&Dir,dest| { dir.copy_to(dest); } ! "Copying dir";
+)]
+fn dir_copy_to_file_in_the_way(copy_to:_) {
+    let (_, root, dir) = dir_fix();
+    dir.file("sub/file.txt").create();
+
+    let dest = root.dir("dest");
+    dest.file("sub").create();
+    copy_to(&dir, &dest);
+}
+
+// remove ------------------------------------------------------------------------------------------
+
+#[test_args(
+    safe: |dir:&Dir| { dir.remove_result().unwrap(); };
+    easy: |dir:&Dir| { dir.remove(); };
+)]
+fn dir_remove(remove:_) {
+    let (_, root, dir) = dir_fix();
+
+    //remove-absent
+    remove(&dir);
+
+    //remove-existing
+    dir.dir("sub").file("file").create();
+    remove(&dir);
+    assert!(!dir.path().exists());
+    assert!(root.dir("nested").path().exists());
+
+    //remove-link
+    let target = root.dir("target").created();
+    target.file("file").create();
+    let link = root.dir("link").linked_to(&target);
+    remove(&link);
+    assert!(std::fs::symlink_metadata(link.path()).is_err());
+    assert!(target.file("file").path().exists());
+}
+
+#[test_args(
+    safe: |dir:&Dir| { dir.clean_result().unwrap(); };
+    easy: |dir:&Dir| { dir.clean(); };
+)]
+fn dir_clean(clean:_) {
+    let (_, root, dir) = dir_fix();
+
+    //clean-absent
+    clean(&dir);
+    assert!(dir.path().is_dir());
+
+    //clean-existing
+    let target = root.dir("target").created();
+    target.file("file").create();
+    dir.dir("sub").file("file").create();
+    dir.file("file").create();
+    dir.dir("link").link_to(&target);
+    clean(&dir);
+    assert!(dir.path().is_dir());
+    assert_eq!(0, std::fs::read_dir(dir.path()).unwrap().count());
+    assert!(target.file("file").path().exists());
+}
+
+// eq ----------------------------------------------------------------------------------------------
+
+#[test]
+fn dir_eq_path() {
+    let dir = Dir::new("/foo/bar");
+
+    assert_eq!(dir, PathBuf::from("/foo/bar"));
+    assert_eq!(dir, *Path::new("/foo/bar"));
+    assert_eq!(PathBuf::from("/foo/bar"), dir);
+    assert_eq!(*Path::new("/foo/bar"), dir);
+    assert_eq!(dir, PathBuf::from("/foo/baz/../bar/."));
+    assert_ne!(dir, PathBuf::from("/foo/baz"));
+    assert_ne!(dir, PathBuf::from("foo/bar"));
+    assert_ne!(dir, PathBuf::from("/../foo/bar"));
+}
+
 // ops ---------------------------------------------------------------------------------------------
 
 #[test]
@@ -253,16 +504,45 @@ fn dir_add_file() {
     assert_eq!(None, foobaz.next());
 }
 
+// unit --------------------------------------------------------------------------------------------
+
+#[test]
+fn dir_unit_accessors() {
+    let (_, root, _) = dir_fix();
+    root.file("foo/bar.txt").create();
+
+    let mut units = root.content("**").sorted().into_iter();
+    let dir = units.next().unwrap();
+    let file = units.next().unwrap();
+
+    assert_eq!((true, false), (dir.is_dir(), dir.is_file()));
+    assert_eq!(Some(&root.dir("foo")), dir.as_dir());
+    assert_eq!(None, dir.as_file());
+
+    assert_eq!((false, true), (file.is_dir(), file.is_file()));
+    assert_eq!(Some(&root.file("foo/bar.txt")), file.as_file());
+    assert_eq!(None, file.as_dir());
+}
+
+#[test]
+fn dir_unit_display() {
+    let dir = Dir::new("/foo");
+    let file = dir.file("bar.txt");
+    assert_eq!(dir.to_string(), Unit::Dir(dir.clone()).to_string());
+    assert_eq!(file.to_string(), format!("{}", Unit::File(file.clone())));
+}
+
 // glob --------------------------------------------------------------------------------------------
 
+// Link foo/bar2 to foo/bar1 is listed, but it's content is counted only once through foo/bar1
 #[test_args(
     none: "**/*.jpg", 0, 0;
     level1: "*", 1, 1;
     level2: "*/*", 2, 0;
-    level3: "*/*/*", 2, 4;
+    level3: "*/*/*", 1, 2;
     bar1: "**/bar1/*", 1, 2;
-    rs: "**/*.rs", 0, 5;
-    all: "**", 5, 9
+    rs: "**/*.rs", 0, 3;
+    all: "**", 4, 5
 )]
 fn dir_content_count(glob: &str, dirs: usize, files: usize) {
     let temp = tempfile::tempdir().unwrap();
@@ -286,6 +566,224 @@ fn dir_content_count(glob: &str, dirs: usize, files: usize) {
     assert_eq!(dirs+files, cycle.content(glob).into_iter().count());
 }
 
+#[test]
+fn dir_content_cycles() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path()).dir("root");
+    let external = Dir::new(temp.path()).dir("external");
+
+    root.file("foo/bar.rs").create();
+    external.file("ext.rs").create();
+    root.dir("foo/self").link_to(&root.dir("foo"));
+    root.dir("foo/back").link_to(&root);
+    root.dir("ext1").link_to(&external);
+    root.dir("ext2").link_to(&external);
+    external.dir("loop").link_to(&root);
+
+    let files = root.files("**").into_iter()
+        .map(|f| f.path().canonicalize().unwrap())
+        .collect::<Vec<_>>();
+    let unique = files.iter().collect::<std::collections::HashSet<_>>();
+    assert_eq!(2, files.len(), "{:?}", files);
+    assert_eq!(files.len(), unique.len(), "{:?}", files);
+
+    // Links to already walked directories are listed, but not walked again
+    let mut dirs = root.dirs("**").into_iter()
+        .map(|d| d.path().strip_prefix(root.path()).unwrap().to_owned())
+        .collect::<Vec<_>>();
+    dirs.sort();
+    assert_eq!(vec![Path::new("ext1"), Path::new("ext2"), Path::new("foo")], dirs);
+}
+
+#[test_args(
+    follow: true, 3, 3;
+    opaque: false, 2, 2;
+)]
+fn dir_content_follow_links(follow: bool, dirs: usize, files: usize) {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path()).dir("root");
+    let external = Dir::new(temp.path()).dir("external");
+
+    root.file("foo/bar.rs").create();
+    external.file("ext/ext.rs").create();
+    root.dir("linked").link_to(&external);
+    root.file("link.rs").link_to(&root.file("foo/bar.rs"));
+
+    assert_eq!(dirs, root.dirs("**").follow_links(follow).into_iter().count());
+    assert_eq!(files, root.files("**").follow_links(follow).into_iter().count());
+    assert_eq!(dirs + files, root.content("**").follow_links(follow).into_iter().count());
+    assert!(root.dirs("*").follow_links(follow).into_iter().any(|d| d.path().ends_with("linked")));
+}
+
+#[test_args(
+    star: "*", None, None, 2;
+    all_max1: "**", None, Some(1), 2;
+    all_max2: "**", None, Some(2), 4;
+    all_min2: "**", Some(2), None, 3;
+    all_min2_max2: "**", Some(2), Some(2), 2;
+    rs_min2: "**/*.rs", Some(2), None, 2;
+    star_min2: "*", Some(2), None, 0;
+)]
+fn dir_content_depth(glob: &str, min: Option<usize>, max: Option<usize>, count: usize) {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path());
+
+    root.file("root.rs").create();
+    root.file("foo/foo.rs").create();
+    root.file("foo/bar/bar.rs").create();
+
+    let mut content = root.content(glob);
+    if let Some(depth) = min {
+        content = content.min_depth(depth);
+    }
+    if let Some(depth) = max {
+        content = content.max_depth(depth);
+    }
+    assert_eq!(count, content.into_iter().count());
+}
+
+#[test]
+fn dir_content_sorted() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path());
+
+    for path in ["b/z.rs", "a.rs", "b/a/x.rs", "c.rs", "b.rs"] {
+        root.file(path).create();
+    }
+
+    let paths = root.content("**").sorted().relative_paths(&root);
+    let mut expected = paths.clone();
+    expected.sort();
+    assert_eq!(expected, paths);
+    assert_eq!(PathBuf::from("a.rs"), paths[0]);
+    assert_eq!(PathBuf::from("c.rs"), paths[paths.len() - 1]);
+}
+
+#[test]
+fn dir_content_sorted_by_mtime() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path());
+    let now = SystemTime::now();
+
+    for (path, age) in [("b.rs", 10), ("a.rs", 30), ("sub/c.rs", 20), ("d.rs", 20)] {
+        let file = root.file(path).created();
+        let time = filetime::FileTime::from_system_time(now - Duration::from_secs(age));
+        filetime::set_file_mtime(file.path(), time).unwrap();
+    }
+
+    let paths = root.files("**").sorted_by_mtime().relative_paths(&root);
+    assert_eq!(vec!["a.rs", "d.rs", "sub/c.rs", "b.rs"], paths.iter()
+        .map(|p| p.to_str().unwrap().replace('\\', "/"))
+        .collect::<Vec<_>>());
+}
+
+#[test]
+fn dir_content_into_set() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path());
+    root.file("foo/bar.rs").create();
+    root.file("baz.rs").create();
+
+    let files: Set<File> = root.files("**").into();
+    let dirs: Set<Dir> = root.dirs("**").into();
+    let units: Set<Unit> = root.content("**").into();
+    root.file("new.rs").create();
+
+    assert_eq!(2, files.clone().into_iter().count());
+    assert_eq!(1, dirs.into_iter().count());
+    assert_eq!(3, units.into_iter().count());
+    assert_eq!(root.files("**/*.rs").exclude("new.rs").timestamp(), files.timestamp());
+}
+
+#[test]
+fn dir_content_modified_after() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path());
+    let now = SystemTime::now();
+
+    for (path, age) in [("old.rs", 30), ("output.txt", 20), ("new.rs", 10), ("new.js", 10)] {
+        let file = root.file(path).created();
+        let time = filetime::FileTime::from_system_time(now - Duration::from_secs(age));
+        filetime::set_file_mtime(file.path(), time).unwrap();
+    }
+
+    let output = root.file("output.txt");
+    let since = |time| root.files("*.rs").modified_after(time).relative_paths(&root);
+    assert_eq!(vec![PathBuf::from("new.rs")], since(now - Duration::from_secs(20)));
+    assert_eq!(Vec::<PathBuf>::new(), since(now - Duration::from_secs(10)));
+    assert_eq!(2, root.files("*").modified_since_resource(&output).into_iter().count());
+    let missing = root.file("missing.txt");
+    assert_eq!(2, root.files("*.rs").modified_since_resource(&missing).into_iter().count());
+}
+
+#[test]
+fn dir_content_regex() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path());
+
+    for path in ["v1.rs", "v12.rs", "vx.rs", "api/v2.rs", "api/v2.js", "api/v3.rs"] {
+        root.file(path).create();
+    }
+
+    let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+    assert_eq!(4, root.files_regex(r"v\d+\.rs$").into_iter().count());
+    assert_eq!(paths(&["v1.rs", "v12.rs"]),
+        root.files_regex(r"^v\d+\.rs$").sorted().relative_paths(&root));
+    assert_eq!(paths(&["api/v2.rs"]),
+        root.files_regex(r"^api/v\d").exclude("**/*.js").exclude_regex("3").relative_paths(&root));
+    assert_eq!(paths(&["api/v2.js", "vx.rs"]),
+        root.files("*.rs").exclude_regex(r"^v\d").include_regex(r"\.js$").sorted()
+            .relative_paths(&root));
+}
+
+#[test_args(
+    none: "**/*.jpg";
+    level1: "*";
+    level3: "*/*/*";
+    all: "**"
+)]
+fn dir_stats(glob: &str) {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path());
+
+    root.file("root.rs").create().write_all(b"root").unwrap();
+    root.file("foo/bar1/bar.rs").create().write_all(b"bar").unwrap();
+    root.file("foo/bar1/baz/baz.rs").create();
+    root.dir( "foo/bar2").link_to(&root.dir("foo/bar1"));
+    root.file("foo/link.rs").link_to(&root.file("root.rs"));
+
+    let stats = root.stats(glob);
+    let links = root.content(glob).into_iter()
+        .filter(|u| std::fs::symlink_metadata(u.path()).unwrap().file_type().is_symlink())
+        .count();
+    let bytes = root.files(glob).into_iter().map(|f| f.metadata().len()).sum::<u64>();
+
+    assert_eq!(root.dirs(glob).into_iter().count(), stats.dirs);
+    assert_eq!(root.files(glob).into_iter().count(), stats.files);
+    assert_eq!(links, stats.links);
+    assert_eq!(bytes, stats.total_bytes);
+}
+
+#[test]
+fn dir_size() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path());
+    assert_eq!(0, root.dir("missing").size());
+
+    root.file("root.rs").create().write_all(b"root").unwrap();
+    root.file("foo/bar1/bar.rs").create().write_all(b"bar").unwrap();
+    root.file("foo/bar1/baz/baz.js").create().write_all(b"baz").unwrap();
+    root.dir( "foo/bar2").link_to(&root.dir("foo/bar1"));
+    root.dir( "foo/bar1/cycle").link_to(&root.dir("foo"));
+    root.file("foo/link.rs").link_to(&root.file("root.rs"));
+    root.file("outside.rs").link_to(&root.file("foo/bar1/bar.rs"));
+
+    assert_eq!(10, root.size());
+    assert_eq!(7, root.files("**/*.rs").total_size());
+    assert_eq!(10, root.dir("foo").size());
+    assert_eq!(4, root.dir("foo").files("*.rs").total_size());
+}
+
 #[test]
 fn dir_content_incl_excl() {
     let temp = tempfile::tempdir().unwrap();
@@ -302,6 +800,91 @@ fn dir_content_incl_excl() {
     assert_eq!(1, root.content("**").exclude("*.rs").exclude("baz*").into_iter().count());
 }
 
+#[test]
+fn dir_content_case_insensitive() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path());
+    root.file("a.txt").create();
+    root.file("b.TXT").create();
+    root.file("c.Rs").create();
+
+    assert_eq!(vec![root.file("b.TXT")], root.files("*.TXT").into_iter().collect::<Vec<_>>());
+    assert_eq!(2, root.files("*.TXT").case_insensitive(true).into_iter().count());
+    assert_eq!(1, root.files("*.TXT").case_insensitive(true).case_insensitive(false).into_iter().count());
+    assert_eq!(3, root.files("*.TXT").case_insensitive(true).include("*.rs").into_iter().count());
+    assert_eq!(1, root.files("**").exclude("*.txt").case_insensitive(true).into_iter().count());
+    assert_eq!(0, root.files_regex("txt$").case_insensitive(true).exclude("*.TXT").into_iter().count());
+}
+
+#[cfg(feature = "ignore")]
+#[test]
+fn dir_content_respect_gitignore() {
+    let temp = tempfile::tempdir().unwrap();
+    let repo = Dir::new(temp.path());
+    repo.dir(".git").create();
+    repo.file(".gitignore").rewrite("target/\n*.swp\n");
+    let root = repo.dir("webapp");
+    root.file(".gitignore").rewrite("node_modules\n!keep.swp\n");
+    for name in ["index.js", "index.js.swp", "keep.swp", "node_modules/lib.js", "target/out.js"] {
+        root.file(name).create();
+    }
+
+    let all = root.files("**").sorted().respect_gitignore(false).into_iter().count();
+    assert_eq!(6, all);
+    let kept = root.files("**").sorted().respect_gitignore(true).into_iter().collect::<Vec<_>>();
+    assert_eq!(vec![root.file(".gitignore"), root.file("index.js"), root.file("keep.swp")], kept);
+    assert_eq!(0, repo.dirs("**/.git").respect_gitignore(true).into_iter().count());
+}
+
+#[test]
+fn dir_content_first_single() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path());
+    root.file("dist/app.wasm").create();
+    root.file("dist/app.js").create();
+    root.file("dist/vendor.js").create();
+
+    assert_eq!(Some(root.dir("dist")), root.dirs("*").first());
+    assert_eq!(None, root.files("*.css").first());
+    assert_eq!(Some(root.file("dist/app.js")), root.files("**/*.js").sorted().first());
+
+    assert_eq!(root.file("dist/app.wasm"), root.files("**/*.wasm").single());
+    assert_eq!(root.dir("dist"), root.dirs("**").single_result().unwrap());
+
+    let err = root.files("**/*.css").single_result().unwrap_err().to_string();
+    assert_eq!(format!("No entry of {} matches", root), err);
+    let err = root.files("**/*.js").sorted().single_result().unwrap_err().to_string();
+    let (app, vendor) = (root.file("dist/app.js"), root.file("dist/vendor.js"));
+    assert_eq!(format!("2 entries match: {}, {}", app, vendor), err);
+}
+
+#[test]
+#[should_panic(expected = "Finding single entry in")]
+fn dir_content_single_failure() {
+    let temp = tempfile::tempdir().unwrap();
+    Dir::new(temp.path()).files("**").single();
+}
+
+#[test]
+fn dir_content_incl_excl_all() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path());
+
+    for name in ["main.rs", "Cargo.toml", "README.md", "app.js", "nested/lib.rs", "nested/test.md"] {
+        root.file(name).create();
+    }
+
+    let chained = root.files("*.rs").include("*.toml").include("**/*.md").exclude("nested/*").sorted();
+    let all = root.files("*.rs").include_all(["*.toml", "**/*.md"])
+        .exclude_all(vec!["nested/*".to_owned()]).sorted();
+    assert_eq!(chained.into_iter().collect::<Vec<_>>(), all.clone().into_iter().collect::<Vec<_>>());
+    assert_eq!(vec![root.file("Cargo.toml"), root.file("README.md"), root.file("main.rs")],
+        all.into_iter().collect::<Vec<_>>());
+
+    let none: [&str; 0] = [];
+    assert_eq!(6, root.files("**").include_all(none).exclude_all(none).into_iter().count());
+}
+
 #[test]
 fn dir_content_paths() {
     let temp = tempfile::tempdir().unwrap();
@@ -316,6 +899,71 @@ fn dir_content_paths() {
     assert_eq!(Unit::File(file), root.content("**/bar").into_iter().next().unwrap());
 }
 
+#[test]
+fn dir_content_relative_paths() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path());
+
+    let base = root.dir("foo/bar").created();
+    base.file("baz.rs").create();
+    base.file("qux/quux.rs").create();
+
+    let mut paths = base.files("**").relative_paths(&root);
+    paths.sort();
+    assert_eq!(vec![PathBuf::from("foo/bar/baz.rs"), PathBuf::from("foo/bar/qux/quux.rs")], paths);
+    assert_eq!(vec![PathBuf::from("foo/bar/qux")], base.dirs("**").relative_paths(&root));
+    assert_eq!(vec![PathBuf::from("baz.rs")], base.content("*.rs").relative_paths(&base));
+    assert_eq!(Vec::<PathBuf>::new(), base.files("**").relative_paths(&root.dir("other")));
+
+    let other = root.dir("other");
+    let paths = base.files("*").relative_paths_result(&base).unwrap();
+    assert_eq!(vec![PathBuf::from("baz.rs")], paths);
+    let err = base.files("*").relative_paths_result(&other).unwrap_err().to_string();
+    assert_eq!(format!("Path {} is not under {}", base.file("baz.rs"), other), err);
+}
+
+#[test]
+fn dir_content_relative_paths_warning() {
+    // Same location in the child process emitting the directives
+    let root = Dir::new(env!("CARGO_TARGET_TMPDIR")).dir("dir_content_relative_paths_warning");
+    let base = root.dir("base").created();
+    let baz = base.file("baz.rs").created();
+    let other = root.dir("other");
+
+    assert_eq!(
+        vec![format!("cargo:warning=Path {} is not under {}", baz, other)],
+        directives("dir_content_relative_paths_warning", || {
+            assert_eq!(Vec::<PathBuf>::new(), base.files("*").relative_paths(&other));
+        })
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn dir_content_for_each_run() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path());
+    let src = root.dir("src");
+    let out = root.dir("out");
+
+    let foo = src.file("foo.css").created();
+    src.file("bar/bar.css").create();
+
+    let run = || src.files("**/*.css").for_each_run(
+        |input| out.file(input.path().strip_prefix(src.path()).unwrap()),
+        |input, output| Cmd::new("cp").arg(input.path()).arg(output.path())
+    );
+
+    assert_eq!(2, run());
+    assert_eq!(true, out.file("foo.css").path().exists());
+    assert_eq!(true, out.file("bar/bar.css").path().exists());
+    assert_eq!(0, run());
+
+    let future = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+    filetime::set_file_mtime(foo.path(), filetime::FileTime::from_system_time(future)).unwrap();
+    assert_eq!(1, run());
+}
+
 #[test]
 fn dir_content_timestamp() {
     let temp = tempfile::tempdir().unwrap();
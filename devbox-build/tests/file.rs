@@ -1,7 +1,16 @@
+#![allow(clippy::bool_assert_comparison)]
+
 use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use devbox_build::*;
 use devbox_test_args::args;
 
+fn aged(file: &File, secs: u64) -> Option<std::time::SystemTime> {
+    let time = std::time::SystemTime::now() - std::time::Duration::from_secs(secs);
+    filetime::set_file_mtime(file.path(), filetime::FileTime::from_system_time(time)).unwrap();
+    file.timestamp()
+}
+
 fn file_fix() -> (tempfile::TempDir, Dir, File) {
     let temp = tempfile::tempdir().unwrap();
     let root = Dir::new(temp.path());
@@ -9,6 +18,39 @@ fn file_fix() -> (tempfile::TempDir, Dir, File) {
     (temp, root, file)
 }
 
+// new ---------------------------------------------------------------------------------------------
+
+#[args(
+    path: |p| File::from(Path::new(p));
+    buff: |p| File::from(PathBuf::from(p));
+    strr: |p| File::from(p);
+    safe: |p| File::new(p).unwrap();
+)]
+#[args(
+    simple: "/foo/bar/baz.txt";
+    resolv: "/foo/../foo/bar/baz.txt/x/y/../..";
+    nonabs_simple: "foo/bar/baz.txt" ! "is not absolute";
+    nonabs_resolv: "/foo/../../x/foo/bar/baz.txt" ! "is not absolute"
+)]
+#[test]
+fn file_from(from:_, path:_) {
+    assert_eq!(PathBuf::from("/foo/bar/baz.txt"), from(path).path());
+}
+
+// exists ------------------------------------------------------------------------------------------
+
+#[test]
+fn file_exists() {
+    let (_, root, file) = file_fix();
+    assert!(!file.exists());
+
+    file.create();
+    assert!(file.exists());
+    assert!(root.file("link").linked_to(&file).exists());
+    assert!(!root.file("nested").exists());
+    assert!(!root.file("dangling").linked_to(&root.file("none")).exists());
+}
+
 // create ------------------------------------------------------------------------------------------
 
 #[args(
@@ -32,6 +74,75 @@ fn file_create(create:_) {
     assert_eq!("", std::fs::read_to_string(file.path()).unwrap());
 }
 
+// append ------------------------------------------------------------------------------------------
+
+#[args(
+    safe: |file:&File| { file.append_result().unwrap() };
+    easy: |file:&File| { file.append() };
+)]
+#[test]
+fn file_append(append:_) {
+    let (_, _, file) = file_fix();
+
+    //append-create
+    append(&file).write_all(b"foo").unwrap();
+    assert_eq!("foo", file.read_to_string());
+
+    //append-existing
+    append(&file).write_all(b"bar").unwrap();
+    assert_eq!("foobar", file.read_to_string());
+}
+
+#[args(
+    safe: |file:&File, bytes:&str| { file.append_bytes_result(bytes).unwrap() };
+    easy: |file:&File, bytes:&str| { file.append_bytes(bytes) };
+)]
+#[test]
+fn file_append_bytes(append_bytes:_) {
+    let (_, _, file) = file_fix();
+
+    append_bytes(&file, "header\n");
+    for entry in ["one", "two"] {
+        append_bytes(&file, entry);
+    }
+    assert_eq!("header\nonetwo", file.read_to_string());
+}
+
+// set_executable ----------------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[args(
+    safe: |file:&File, yes| { file.set_executable_result(yes).unwrap() };
+    easy: |file:&File, yes| { file.set_executable(yes) };
+)]
+#[test]
+fn file_set_executable(set_executable:_) {
+    use std::os::unix::fs::PermissionsExt;
+    let (_, _, file) = file_fix();
+    let mode = |file:&File| file.path().metadata().unwrap().permissions().mode() & 0o777;
+
+    file.rewrite("#!/bin/sh\necho generated\n");
+    std::fs::set_permissions(file.path(), std::fs::Permissions::from_mode(0o640)).unwrap();
+
+    set_executable(&file, true);
+    assert_eq!(0o750, mode(&file));
+    assert_eq!("generated", Cmd::new(file.path()).stdout());
+
+    set_executable(&file, false);
+    assert_eq!(0o640, mode(&file));
+}
+
+#[cfg(unix)]
+#[args(
+    safe: |file:&File| { assert!(file.set_executable_result(true).is_err()) };
+    easy: |file:&File| { file.set_executable(true) } ! "Setting executable file";
+)]
+#[test]
+fn file_set_executable_missing(set_executable:_) {
+    let (_, _, file) = file_fix();
+    set_executable(&file);
+}
+
 // link_to -----------------------------------------------------------------------------------------
 
 #[args(
@@ -84,6 +195,116 @@ fn file_link_to_overwrite_file(link_to:_) {
     link_to(&link, &file);
 }
 
+// ensure_link_to ----------------------------------------------------------------------------------
+
+#[args(
+    safe: |file:&File,to| { file.ensure_link_to_result(to).unwrap(); };
+    easy: |file:&File,to| { file.ensure_link_to(to); };
+)]
+#[test]
+fn file_ensure_link_to(ensure_link_to:_) {
+    let (_, root, file) = file_fix();
+    file.create();
+    let foe = root.file("foe").created();
+    let link = root.file("nested/link");
+
+    //missing
+    ensure_link_to(&link, &file);
+    assert_eq!(file.path(), std::fs::read_link(link.path()).unwrap());
+
+    //correct
+    ensure_link_to(&link, &file);
+    assert_eq!(file.path(), std::fs::read_link(link.path()).unwrap());
+
+    //elsewhere
+    ensure_link_to(&link, &foe);
+    assert_eq!(foe.path(), std::fs::read_link(link.path()).unwrap());
+}
+
+#[args(
+    safe: |file:&File,to| { file.ensure_link_to_result(to).expect("Link"); } ! "Link";
+    easy: |file:&File,to| { file.ensure_link_to(to); }                    ! "Ensuring link";
+)]
+#[test]
+fn file_ensure_link_to_overwrite_file(ensure_link_to:_) {
+    let (_, root, file) = file_fix();
+    file.create();
+
+    let link = root.file("nested/link").created();
+    ensure_link_to(&link, &file);
+}
+
+#[test]
+fn file_ensure_link_to_dangling() {
+    let (_, root, file) = file_fix();
+    let link = root.file("nested/link").linked_to(&root.file("gone"));
+
+    link.ensure_link_to(&file);
+    assert_eq!(file.path(), std::fs::read_link(link.path()).unwrap());
+}
+
+// hard_link_to ------------------------------------------------------------------------------------
+
+#[args(
+    forc: |file:&File,to| { file.hard_link_to_result(to, true).unwrap(); };
+    safe: |file:&File,to| { file.hard_link_to_result(to, false).unwrap(); };
+    easy: |file:&File,to| { file.hard_link_to(to); };
+)]
+#[test]
+fn file_hard_link_to(hard_link_to:_) {
+    let (_, root, file) = file_fix();
+    file.create();
+
+    let link = root.file("nested/link");
+    hard_link_to(&link, &file);
+    assert!(!std::fs::symlink_metadata(link.path()).unwrap().file_type().is_symlink());
+
+    std::fs::write(file.path(), "shared").unwrap();
+    assert_eq!("shared", std::fs::read_to_string(link.path()).unwrap());
+}
+
+#[args(
+    forc: |file:&File,to| { file.hard_link_to_result(to, true).expect("Link"); };
+    safe: |file:&File,to| { file.hard_link_to_result(to, false).expect("Link"); } ! "Link";
+    easy: |file:&File,to| { file.hard_link_to(to); } ! "Creating hard link";
+)]
+#[test]
+fn file_hard_link_to_overwrite_file(hard_link_to:_) {
+    let (_, root, file) = file_fix();
+    file.create();
+    std::fs::write(file.path(), "target").unwrap();
+
+    let link = root.file("nested/link").created();
+    hard_link_to(&link, &file);
+    assert_eq!("target", std::fs::read_to_string(link.path()).unwrap());
+}
+
+#[args(
+    safe: |file:&File,to| { file.hard_link_to_result(to, false).expect("Link"); } ! "Link";
+    easy: |file:&File,to| { file.hard_link_to(to); } ! "Creating hard link";
+)]
+#[test]
+fn file_hard_link_to_nonexistent(hard_link_to:_) {
+    let (_, root, file) = file_fix();
+    hard_link_to(&root.file("nested/link"), &file);
+}
+
+#[args(
+    safe: |file:&File,to| { file.link_or_hard_link_to_result(to, false).unwrap(); };
+    easy: |file:&File,to| { file.link_or_hard_link_to(to); };
+)]
+#[test]
+fn file_link_or_hard_link_to(link_to:_) {
+    let (_, root, file) = file_fix();
+    file.create();
+
+    let link = root.file("nested/link");
+    link_to(&link, &file);
+    link_to(&link, &file);
+
+    assert_eq!(file.path(), std::fs::read_link(link.path()).unwrap());
+}
+
 // link_from_inside --------------------------------------------------------------------------------
 
 #[args(
@@ -136,6 +357,40 @@ fn file_link_from_inside_overwrite_link(link_from_inside:_) {
     link_from_inside(&target, &dir);
 }
 
+// copy_to -----------------------------------------------------------------------------------------
+
+#[args(
+    safe: |file:&File, dest| { file.copy_to_result(dest).unwrap(); };
+    easy: |file:&File, dest| { file.copy_to(dest); };
+    bild: |file:&File, dest| { file.clone().copied_to(dest); }
+)]
+#[test]
+fn file_copy_to(copy_to:_) {
+    let (_, root, file) = file_fix();
+    file.create().write_all(b"foo").unwrap();
+
+    //copy-create
+    let dest = root.file("other/dir/copy.txt");
+    copy_to(&file, &dest);
+    assert_eq!("foo", std::fs::read_to_string(dest.path()).unwrap());
+    assert_eq!("foo", std::fs::read_to_string(file.path()).unwrap());
+
+    //copy-overwrite
+    file.create().write_all(b"bar").unwrap();
+    copy_to(&file, &dest);
+    assert_eq!("bar", std::fs::read_to_string(dest.path()).unwrap());
+}
+
+#[args(
+    safe: |file:&File, dest| { file.copy_to_result(dest).expect("Copying file"); } ! "Copying file";
+    easy: |file:&File, dest| { file.copy_to(dest); } ! "Copying file"
+)]
+#[test]
+fn file_copy_to_nonexistent(copy_to:_) {
+    let (_, root, file) = file_fix();
+    copy_to(&file, &root.file("copy.txt"));
+}
+
 // metadata ----------------------------------------------------------------------------------------
 
 #[args(
@@ -188,6 +443,179 @@ fn file_open_nonexistent(open:_) {
      open(&file);
 }
 
+// read --------------------------------------------------------------------------------------------
+
+#[args(
+    safe: |file:&File| { file.read_result().unwrap() };
+    easy: |file:&File| { file.read() };
+    text: |file:&File| { file.read_to_string().into_bytes() };
+    tsaf: |file:&File| { file.read_to_string_result().unwrap().into_bytes() };
+)]
+#[test]
+fn file_read(read:_) {
+    let (_, _, file) = file_fix();
+    file.rewrite("foo");
+    assert_eq!(b"foo".to_vec(), read(&file));
+}
+
+#[args(
+    safe: |file:&File| { file.read_result().expect("Reading file"); } ! "Reading file";
+    easy: |file:&File| { file.read(); } ! "Reading file";
+    text: |file:&File| { file.read_to_string(); } ! "Reading file";
+    tsaf: |file:&File| { file.read_to_string_result().expect("Reading file"); } ! "Reading file";
+)]
+#[test]
+fn file_read_nonexistent(read:_) {
+    let (_, _, file) = file_fix();
+    read(&file);
+}
+
+#[test]
+#[should_panic(expected = "Reading file")]
+fn file_read_to_string_invalid() {
+    let (_, _, file) = file_fix();
+    file.rewrite([0xff, 0xfe]);
+    assert_eq!(vec![0xff, 0xfe], file.read());
+    file.read_to_string();
+}
+
+// remove ------------------------------------------------------------------------------------------
+
+#[args(
+    safe: |file:&File| { file.remove_result().unwrap(); };
+    easy: |file:&File| { file.remove(); };
+)]
+#[test]
+fn file_remove(remove:_) {
+    let (_, root, file) = file_fix();
+
+    //remove-absent
+    remove(&file);
+
+    //remove-existing
+    file.create();
+    remove(&file);
+    assert!(!file.path().exists());
+    assert!(root.dir("nested").path().exists());
+
+    //remove-link
+    let target = root.file("target").created();
+    let link = root.file("link").linked_to(&target);
+    remove(&link);
+    assert!(std::fs::symlink_metadata(link.path()).is_err());
+    assert!(target.path().exists());
+}
+
+#[args(
+    safe: |file:&File| { file.remove_result().expect("Removing file"); } ! "Removing file";
+    easy: |file:&File| { file.remove(); } ! "Removing file";
+)]
+#[test]
+fn file_remove_dir(remove:_) {
+    let (_, root, _) = file_fix();
+    root.dir("dir").create();
+    remove(&root.file("dir"));
+}
+
+// rewrite -----------------------------------------------------------------------------------------
+
+#[test]
+fn file_rewrite_result() {
+    let (_, _, file) = file_fix();
+
+    //absent
+    assert_eq!(true, file.rewrite_result("foo").unwrap());
+    assert_eq!("foo", file.read_to_string());
+    let written = aged(&file, 100);
+
+    //same
+    assert_eq!(false, file.rewrite_result("foo").unwrap());
+    assert_eq!(written, file.timestamp());
+
+    //different
+    assert_eq!(true, file.rewrite_result("bar").unwrap());
+    assert_eq!("bar", file.read_to_string());
+    assert_ne!(written, file.timestamp());
+}
+
+#[test]
+fn file_rewrite() {
+    let (_, _, file) = file_fix();
+    file.rewrite("foo");
+    file.rewrite("foo");
+    assert_eq!("foo", file.read_to_string());
+}
+
+#[cfg(unix)]
+#[test]
+fn file_rewrite_atomic() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (_, root, file) = file_fix();
+    file.rewrite("foo");
+    std::fs::set_permissions(file.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+    let link = root.file("link").linked_to(&file);
+
+    //no temporary files left behind, link and permissions kept
+    assert_eq!(true, link.rewrite_result("bar").unwrap());
+    assert_eq!("bar", file.read_to_string());
+    assert_eq!(file.path(), std::fs::read_link(link.path()).unwrap());
+    assert_eq!(0o755, file.metadata().permissions().mode() & 0o777);
+    assert_eq!(vec![file.clone()], root.files("nested/*").into_iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn file_rewrite_concurrent() {
+    let (_, root, file) = file_fix();
+    let contents: Vec<String> = (0..8).map(|i| format!("content {}", i).repeat(1000)).collect();
+
+    std::thread::scope(|scope| {
+        for content in &contents {
+            let file = file.clone();
+            scope.spawn(move || (0..20).for_each(|_| { file.rewrite_result(content).unwrap(); }));
+        }
+    });
+
+    assert!(contents.contains(&file.read_to_string()));
+    assert_eq!(vec![file.clone()], root.files("nested/*").into_iter().collect::<Vec<_>>());
+}
+
+#[test]
+#[should_panic(expected = "Writing text")]
+fn file_rewrite_failure() {
+    let (_, root, _) = file_fix();
+    root.file("dir").rewrite("foo");
+    root.file("dir/nested").rewrite("foo");
+}
+
+#[test]
+fn file_rewrite_template() {
+    let (_, _, file) = file_fix();
+    let mut vars = std::collections::HashMap::new();
+    vars.insert("NAME", "devbox".to_owned());
+    vars.insert("VERSION", "1.0".to_owned());
+
+    let template = "pub const NAME: &str = \"{{NAME}}\";\npub const VERSION: &str = \"{{ VERSION }}\";\n";
+    assert_eq!(true, file.rewrite_template(template, &vars));
+    assert_eq!(false, file.rewrite_template(template, &vars));
+    assert_eq!("pub const NAME: &str = \"devbox\";\npub const VERSION: &str = \"1.0\";\n", file.read_to_string());
+
+    let err = file.rewrite_template_result("{{NAME}} {{VERSOIN}}", &vars).unwrap_err();
+    assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+    assert_eq!("Template variable VERSOIN NOT FOUND", err.to_string());
+
+    let err = file.rewrite_template_result("{{NAME}} {{VERSION", &vars).unwrap_err();
+    assert_eq!("Template placeholder {{VERSION NOT TERMINATED", err.to_string());
+    assert!(file.read_to_string().starts_with("pub const NAME"));
+}
+
+#[test]
+#[should_panic(expected = "Writing template")]
+fn file_rewrite_template_failure() {
+    let (_, _, file) = file_fix();
+    file.rewrite_template("{{MISSING}}", &std::collections::HashMap::new());
+}
+
 // timestamp ---------------------------------------------------------------------------------------
 
 #[test]
@@ -200,6 +628,34 @@ fn file_timestamp() {
                file.timestamp().unwrap());
 }
 
+// is_newer_than -----------------------------------------------------------------------------------
+
+#[test]
+fn file_is_newer_than() {
+    let (_, root, file) = file_fix();
+    let src = root.file("src.txt");
+
+    //missing
+    assert_eq!(false, file.is_newer_than(&src));
+    src.create();
+    assert_eq!(false, file.is_newer_than(&src));
+    file.create();
+    assert_eq!(false, file.is_newer_than(&root.file("missing.txt")));
+
+    //older, same, newer
+    aged(&file, 200);
+    aged(&src, 100);
+    assert_eq!(false, file.is_newer_than(&src));
+    filetime::set_file_mtime(file.path(), filetime::FileTime::from_system_time(src.timestamp().unwrap())).unwrap();
+    assert_eq!(true, file.is_newer_than(&src));
+    aged(&file, 10);
+    assert_eq!(true, file.is_newer_than(&src));
+    assert_eq!(true, file.is_newer_than(&vec![src.clone(), file.clone()]));
+
+    //forced
+    assert_eq!(false, file.is_newer_than(&Always));
+}
+
 // touch -------------------------------------------------------------------------------------------
 
 #[args(
@@ -224,6 +680,41 @@ fn file_touch(touch:_) {
     assert_eq!(true, before < std::fs::metadata(file.path()).unwrap().modified().unwrap());
 }
 
+// write_default -----------------------------------------------------------------------------------
+
+#[args(
+    safe: |file:&File, bytes| { file.write_default_result(bytes).unwrap() };
+    easy: |file:&File, bytes| { file.write_default(bytes) };
+)]
+#[test]
+fn file_write_default(write_default:_) {
+    let (_, _, file) = file_fix();
+
+    //absent
+    assert_eq!(true, write_default(&file, "foo"));
+    assert_eq!("foo", std::fs::read_to_string(file.path()).unwrap());
+
+    //existing
+    assert_eq!(false, write_default(&file, "bar"));
+    assert_eq!("foo", std::fs::read_to_string(file.path()).unwrap());
+}
+
+// eq ----------------------------------------------------------------------------------------------
+
+#[test]
+fn file_eq_path() {
+    let file = Dir::new("/foo").file("bar");
+
+    assert_eq!(file, PathBuf::from("/foo/bar"));
+    assert_eq!(file, *Path::new("/foo/bar"));
+    assert_eq!(PathBuf::from("/foo/bar"), file);
+    assert_eq!(*Path::new("/foo/bar"), file);
+    assert_eq!(file, PathBuf::from("/foo/baz/../bar/."));
+    assert_ne!(file, PathBuf::from("/foo/baz"));
+    assert_ne!(file, PathBuf::from("foo/bar"));
+    assert_ne!(file, PathBuf::from("/../foo/bar"));
+}
+
 // ops ---------------------------------------------------------------------------------------------
 
 #[test]
@@ -0,0 +1,74 @@
+#[cfg(not(feature = "log"))]
+mod common;
+
+use devbox_build::*;
+#[cfg(not(feature = "log"))]
+use common::printed;
+
+// verbosity ---------------------------------------------------------------------------------------
+
+#[cfg(not(feature = "log"))]
+#[test]
+fn log_verbosity() {
+    let root = Dir::new(env!("CARGO_TARGET_TMPDIR")).dir("log_verbosity");
+    let lines = printed("log_verbosity", || {
+        assert_eq!(Verbosity::Verbose, verbosity());
+        root.file("verbose.txt").touch();
+
+        set_verbosity(Verbosity::Quiet);
+        assert_eq!(Verbosity::Quiet, verbosity());
+        root.file("quiet.txt").touch();
+        Cmd::new("cargo").arg("--version").run();
+        println!("cargo:warning=still warned");
+
+        set_verbosity(Verbosity::Verbose);
+        root.file("verbose-again.txt").touch();
+    });
+
+    // Test harness prints it's own output on the same line as the first logged one
+    let logged: Vec<&str> = lines.iter()
+        .filter_map(|line| line.find("Touching").or_else(|| line.find("Executing")).map(|i| &line[i..]))
+        .collect();
+    assert_eq!(vec![
+        format!("Touching file: {}", root.file("verbose.txt")),
+        format!("Touching file: {}", root.file("verbose-again.txt")),
+    ], logged);
+    assert!(lines.contains(&"cargo:warning=still warned".to_owned()), "{:?}", lines);
+}
+
+// log feature -------------------------------------------------------------------------------------
+
+#[cfg(feature = "log")]
+#[test]
+fn log_feature() {
+    struct Capture(std::sync::Mutex<Vec<String>>);
+
+    impl log::Log for Capture {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            let line = format!("{} {}: {}", record.level(), record.target(), record.args());
+            self.0.lock().unwrap().push(line);
+        }
+
+        fn flush(&self) {}
+    }
+
+    static CAPTURE: Capture = Capture(std::sync::Mutex::new(vec![]));
+    log::set_logger(&CAPTURE).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+
+    let temp = tempfile::tempdir().unwrap();
+    let file = Dir::new(temp.path()).file("logged.txt");
+    file.touch();
+    set_verbosity(Verbosity::Quiet);
+    file.touch();
+    set_verbosity(Verbosity::Verbose);
+
+    assert_eq!(vec![
+        format!("INFO devbox_build: Touching file: {}", file),
+        format!("INFO devbox_build: Creating file: {}", file),
+    ], *CAPTURE.0.lock().unwrap());
+}
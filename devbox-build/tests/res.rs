@@ -0,0 +1,416 @@
+use std::{cell::Cell, time::{Duration, SystemTime}};
+
+use devbox_build::*;
+
+mod common;
+use common::directives;
+
+fn res_fix() -> (tempfile::TempDir, Dir) {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Dir::new(temp.path());
+    (temp, root)
+}
+
+fn aged<R: Resource + AsRef<std::path::Path>>(res: R, secs: u64) -> R {
+    let time = SystemTime::now() - Duration::from_secs(secs);
+    filetime::set_file_mtime(res.as_ref(), filetime::FileTime::from_system_time(time)).unwrap();
+    res
+}
+
+// newest_input ------------------------------------------------------------------------------------
+
+#[test]
+fn res_newest_input() {
+    let (_, root) = res_fix();
+    let old = aged(root.file("old.txt").created(), 300);
+    let new = aged(root.file("new.txt").created(), 100);
+    let dir = aged(root.dir("dir").created(), 200);
+    let missing = root.file("missing.txt");
+
+    let (name, time) = newest_input(&[&old, &missing, &new, &dir]).unwrap();
+    assert_eq!("new.txt", name);
+    assert_eq!(new.timestamp().unwrap(), time);
+
+    let (name, _) = newest_input(&[&old, &dir.files("*")]).unwrap();
+    assert_eq!("old.txt", name);
+
+    assert_eq!(None, newest_input(&[&missing]));
+    assert_eq!(None, newest_input(&[]));
+}
+
+// name --------------------------------------------------------------------------------------------
+
+#[test]
+fn res_name() {
+    let (_, root) = res_fix();
+    let file = root.file("src/main.rs");
+    let dir = root.dir("src");
+
+    assert_eq!("main.rs", file.name());
+    assert_eq!("src", dir.name());
+    assert_eq!("src", Unit::Dir(dir.clone()).name());
+    assert_eq!("content of src", dir.files("*.rs").name());
+    assert_eq!("<2 resources>", (&file + &file).name());
+    assert_eq!("<0 resources>", Vec::<File>::new().name());
+    assert_eq!("main.rs", Deferred::new(|| file.clone()).name());
+    assert_eq!("Always", Always.name());
+}
+
+// mk_from -----------------------------------------------------------------------------------------
+
+#[test]
+fn res_mk_from_same_sources() {
+    let (_temp, root) = res_fix();
+    let webwrk_pkg = aged(root.file("pkg/app.js").created(), 100);
+    let webwrk_pkl = aged(root.file("pkg/app.lock").created(), 100);
+    let output = aged(root.file("out/app.js").created(), 200);
+
+    let mut built = 0;
+    output.mk_from("Test", &webwrk_pkg + &webwrk_pkl, || built += 1);
+    output.mk_from("Test", &webwrk_pkg, || built += 1);
+    output.mk_from_result("Test", &webwrk_pkg + &webwrk_pkl, || { built += 1; Ok::<(), ()>(()) })
+        .unwrap();
+    output.mk_from_result("Test", &webwrk_pkg, || { built += 1; Ok::<(), ()>(()) }).unwrap();
+    assert_eq!(4, built);
+
+    aged(output.clone(), 0);
+    output.mk_from("Test", &webwrk_pkg + &webwrk_pkl, || built += 1);
+    assert_eq!(Ok(()), output.mk_from_result("Test", &webwrk_pkg + &webwrk_pkl, || Err(())));
+    assert_eq!(4, built);
+}
+
+#[test]
+fn res_mk_from_rebuilt() {
+    let (_temp, root) = res_fix();
+    let input = aged(root.file("in.txt").created(), 100);
+    let output = root.file("out.txt");
+
+    assert!(output.mk_from("Test", &input, || { output.create(); }));
+    assert!(!output.mk_from("Test", &input, || panic!("up to date")));
+    assert!(!output.mk_from_watched("Test", &input, || panic!("up to date")));
+    assert!(output.mk_from("Test", Always, || ()));
+}
+
+#[test]
+fn res_mk_from_outputs() {
+    let (_temp, root) = res_fix();
+    let input = aged(root.file("in.txt").created(), 200);
+    let rs = aged(root.file("out.rs").created(), 100);
+    let dep = aged(root.file("out.d").created(), 300);
+    let missing = root.file("missing.d");
+
+    assert!((&rs + &dep).mk_from("Test", &input, || ()));
+    assert!(vec![rs.clone(), missing].mk_from("Test", &input, || ()));
+    assert!(!vec![rs.clone()].mk_from("Test", &input, || ()));
+    assert!(Set::from(Vec::<File>::new()).mk_from("Test", &input, || ()));
+
+    aged(dep.clone(), 0);
+    assert!(!(&rs + &dep).mk_from("Test", &input, || panic!("up to date")));
+}
+
+#[test]
+fn res_mk_from_path() {
+    let (_temp, root) = res_fix();
+    let input = aged(root.file("in.txt").created(), 100).path().to_owned();
+    let output = root.file("out.txt");
+    let missing = root.path().join("missing.txt");
+
+    assert_eq!("in.txt", input.name());
+    assert_eq!(vec![input.clone()], input.as_path().paths());
+    assert_eq!(input.timestamp(), root.file("in.txt").timestamp());
+    assert_eq!(None, missing.timestamp());
+    assert_eq!(root.timestamp(), root.path().timestamp());
+
+    assert!(output.mk_from("Test", &input, || { output.create(); }));
+    assert!(!output.mk_from("Test", input.clone(), || panic!("up to date")));
+    assert!(root.file("missing.rs").mk_from("Test", &input, || ()));
+}
+
+#[test]
+fn res_mk_from_tuple() {
+    let (_temp, root) = res_fix();
+    let file = aged(root.file("in.txt").created(), 300);
+    let dir = aged(root.dir("src").created(), 100);
+    let output = aged(root.file("out.txt").created(), 200);
+    let missing = root.file("missing.txt");
+    let input = (&file, &dir, root.path());
+
+    assert_eq!("<3 resources>", input.name());
+    assert_eq!(dir.timestamp(), (&file, &dir).timestamp());
+    assert_eq!(vec![file.path(), dir.path(), root.path()], input.paths());
+    assert!((&file, &Always).forces_rebuild());
+    assert_eq!(None, (&missing, &output).output_timestamp());
+
+    assert!(output.mk_from("Test", (&file, &dir), || { aged(output.clone(), 0); }));
+    assert!(!output.mk_from("Test", (&file, &dir), || panic!("up to date")));
+    assert!(output.mk_from("Test", (&file, &Always), || ()));
+    assert!((&output, &missing).mk_from("Test", (&file, &dir), || ()));
+}
+
+// build_parallel ----------------------------------------------------------------------------------
+
+#[test]
+fn res_build_parallel() {
+    let (_, root) = res_fix();
+    let src = aged(root.file("src.txt").created(), 100);
+    let fresh = root.file("fresh.txt").created();
+    let (first, second) = (root.file("first.txt"), root.file("second.txt"));
+    let ran = std::sync::atomic::AtomicUsize::new(0);
+
+    let start = std::time::Instant::now();
+    let steps = vec![
+        BuildStep::new(&first, "first", &src, || {
+            std::thread::sleep(Duration::from_millis(300));
+            first.touch();
+            ran.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }),
+        BuildStep::new(&fresh, "fresh", &src, || panic!("fresh output rebuilt")),
+        BuildStep::new(&second, "second", &Always, || {
+            std::thread::sleep(Duration::from_millis(300));
+            ran.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }),
+    ];
+    assert_eq!(2, build_parallel(4, steps));
+    assert_eq!(2, ran.load(std::sync::atomic::Ordering::SeqCst));
+    assert!(start.elapsed() < Duration::from_millis(550), "{:?}", start.elapsed());
+
+    let steps = vec![BuildStep::new(&first, "first", &src, || panic!("first rebuilt"))];
+    assert_eq!(0, build_parallel(4, steps));
+    assert_eq!(0, build_parallel(0, vec![]));
+}
+
+#[test]
+fn res_build_parallel_failure() {
+    let (_, root) = res_fix();
+    let outputs = (0..4).map(|i| root.file(format!("out{}.txt", i))).collect::<Vec<_>>();
+    let ran = std::sync::atomic::AtomicUsize::new(0);
+
+    let steps = outputs.iter().map(|output| BuildStep::new(output, "failing", &Always, || {
+        ran.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        panic!("step failed");
+    })).collect();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| build_parallel(2, steps)));
+
+    assert_eq!(Some(&"step failed"), result.unwrap_err().downcast_ref::<&str>());
+    assert!(ran.load(std::sync::atomic::Ordering::SeqCst) <= 2);
+}
+
+// Always ------------------------------------------------------------------------------------------
+
+#[test]
+fn res_always() {
+    let (_, root) = res_fix();
+    let input = aged(root.file("in.txt").created(), 200);
+    let output = aged(root.file("out.txt").created(), 100);
+    let mut built = 0;
+
+    for _ in 0..3 {
+        output.mk_from("Test", &input, || built += 100);
+        output.mk_from("Test", Always, || built += 1);
+        output.mk_from("Test", Set::from(vec![Always]) + Always, || built += 1);
+        output.mk_from("Test", Deferred::new(|| Always), || built += 1);
+        output.mk_from_hashed("Test", Always, || built += 1);
+        output.mk_from_result("Test", Always, || { built += 1; Ok::<(), ()>(()) }).unwrap();
+    }
+    assert_eq!(15, built);
+}
+
+// Deferred ----------------------------------------------------------------------------------------
+
+#[test]
+fn res_deferred() {
+    let (_, root) = res_fix();
+    let calls = Cell::new(0);
+    let deferred = Deferred::new(|| {
+        calls.set(calls.get() + 1);
+        root.dir("generated").file("out.txt")
+    });
+
+    assert_eq!(0, calls.get());
+    assert_eq!("Deferred(unresolved)", format!("{:?}", deferred));
+
+    let file = root.dir("generated").created().file("out.txt").created();
+    assert_eq!(file.timestamp(), deferred.timestamp());
+    assert_eq!(&file, deferred.resource());
+    assert_eq!(format!("{:?}", file), format!("{:?}", deferred));
+
+    deferred.timestamp();
+    assert_eq!(1, calls.get());
+}
+
+#[test]
+fn res_deferred_mk_from() {
+    let (_, root) = res_fix();
+    let input = root.file("in.txt");
+    let deferred = Deferred::new(|| input.clone());
+    input.create();
+
+    let output = root.file("out.txt");
+    let mut built = false;
+    output.mk_from("Test", &deferred, || built = true);
+    assert!(built);
+}
+
+// Virtual -----------------------------------------------------------------------------------------
+
+#[test]
+fn res_virtual() {
+    let (_, root) = res_fix();
+    let output = aged(root.file("out.txt").created(), 100);
+    let time = Cell::new(SystemTime::now() - Duration::from_secs(200));
+    let input = Virtual::new(|| Some(time.get())).named("clock");
+    assert_eq!("clock", format!("{:?}", input));
+    assert_eq!("clock", input.name());
+    assert_eq!(None, input.fingerprint());
+
+    let mut built = 0;
+    output.mk_from("Test", &input, || built += 1);
+    assert_eq!(0, built);
+
+    time.set(SystemTime::now());
+    output.mk_from("Test", &input, || built += 1);
+    assert_eq!(1, built);
+}
+
+#[test]
+fn res_virtual_from_fingerprint() {
+    let (_, root) = res_fix();
+    let output = root.dir("res_virtual_from_fingerprint").file("out.txt").created();
+    let head = Cell::new("1234abcd");
+    let input = Virtual::from_fingerprint(|| Some(head.get().as_bytes().to_vec()));
+    assert_eq!(None, input.timestamp());
+    assert_eq!("Virtual", format!("{:?}", input));
+
+    let mut built = 0;
+    output.mk_from_hashed("Test", &input, || built += 1);
+    output.mk_from_hashed("Test", &input, || built += 1);
+    assert_eq!(1, built);
+
+    head.set("5678ef01");
+    output.mk_from_hashed("Test", &input, || built += 1);
+    assert_eq!(2, built);
+}
+
+// Set ---------------------------------------------------------------------------------------------
+
+#[test]
+fn res_set_collection() {
+    let (_, root) = res_fix();
+    let empty: Set<File> = Vec::new().into();
+    assert!(empty.is_empty());
+    assert_eq!(0, empty.len());
+
+    let set: Set<File> = ["a.txt", "b.txt", "c.txt"].iter().map(|name| root.file(name)).collect();
+    assert!(!set.is_empty());
+    assert_eq!(3, set.len());
+    assert_eq!(Some(&root.file("b.txt")), set.iter().nth(1));
+    assert_eq!(3, (&set).into_iter().filter(|file| !file.exists()).count());
+    assert_eq!(vec![root.file("a.txt"), root.file("b.txt"), root.file("c.txt")],
+        set.into_iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn res_set_add_set() {
+    let (_, root) = res_fix();
+    let rust = &root.file("a.rs") + &root.file("b.rs");
+    let toml: Set<File> = vec![root.file("Cargo.toml")].into();
+
+    let inputs = rust.clone() + &toml;
+    assert_eq!(vec![root.file("a.rs"), root.file("b.rs"), root.file("Cargo.toml")],
+        inputs.into_iter().collect::<Vec<_>>());
+
+    let inputs = toml + rust + root.file("c.rs");
+    assert_eq!(vec![root.file("Cargo.toml"), root.file("a.rs"), root.file("b.rs"),
+        root.file("c.rs")], inputs.into_iter().collect::<Vec<_>>());
+}
+
+// rerun_if_changed --------------------------------------------------------------------------------
+
+#[test]
+fn res_rerun_if_changed() {
+    // Same location in the child process emitting the directives
+    let root = Dir::new(env!("CARGO_TARGET_TMPDIR")).dir("res_rerun_if_changed").created();
+    let file = root.file("in.txt").created();
+    let dir = root.dir("src").created();
+    let a = dir.file("a.rs").created();
+    dir.file("c.txt").create();
+
+    let rerun = |res: &dyn AsRef<std::path::Path>| {
+        format!("cargo:rerun-if-changed={}", res.as_ref().display())
+    };
+
+    assert_eq!(
+        vec![rerun(&file), rerun(&dir), rerun(&file), rerun(&a), rerun(&a)],
+        directives("res_rerun_if_changed", || {
+            file.rerun_if_changed();
+            (&dir + &file).rerun_if_changed();
+            dir.files("*.rs").rerun_if_changed();
+            root.file("out.txt").mk_from_watched("Test", dir.files("*.rs"), || ());
+        })
+    );
+}
+
+// fingerprint -------------------------------------------------------------------------------------
+
+#[test]
+fn res_fingerprint() {
+    let (_temp, root) = res_fix();
+    let dir = root.dir("src").created();
+    let file = dir.file("a.txt").created();
+    file.rewrite("a");
+    dir.file("b.rs").rewrite("b");
+
+    assert_eq!(None, root.file("missing.txt").fingerprint());
+    assert_eq!(None, root.dir("missing").fingerprint());
+
+    let (file_print, dir_print, content_print) =
+        (file.fingerprint(), dir.fingerprint(), dir.files("*.rs").fingerprint());
+    assert!(file_print.is_some() && dir_print.is_some() && content_print.is_some());
+
+    aged(file.clone(), 100);
+    assert_eq!(file_print, file.fingerprint());
+    assert_eq!(dir_print, dir.fingerprint());
+
+    file.rewrite("A");
+    assert_ne!(file_print, file.fingerprint());
+    assert_ne!(dir_print, dir.fingerprint());
+    assert_eq!(content_print, dir.files("*.rs").fingerprint());
+
+    dir.file("c.rs").create();
+    assert_ne!(content_print, dir.files("*.rs").fingerprint());
+    assert_eq!(None, vec![file.clone(), root.file("missing.txt")].fingerprint());
+}
+
+#[test]
+fn res_mk_from_hashed() {
+    let (_temp, root) = res_fix();
+    let stamps = root.dir("out");
+    let input = root.file("in.txt");
+    input.rewrite("content");
+    let output = root.file("out.txt");
+
+    let built = Cell::new(0);
+    let build = || output.mk_from_hashed_in(&stamps, "Test", &input, || {
+        output.touch();
+        built.set(built.get() + 1);
+    });
+
+    build();
+    build();
+    assert_eq!(1, built.get());
+    assert_eq!(1, stamps.files("devbox-fingerprints/*").into_iter().count());
+
+    aged(input.clone(), 0);
+    aged(output.clone(), 100);
+    build();
+    assert_eq!(1, built.get(), "Rebuilt on timestamp change only");
+
+    input.rewrite("changed");
+    aged(input.clone(), 200);
+    build();
+    assert_eq!(2, built.get(), "Not rebuilt on content change");
+
+    std::fs::remove_file(output.path()).unwrap();
+    build();
+    assert_eq!(3, built.get(), "Not rebuilt on missing output");
+}
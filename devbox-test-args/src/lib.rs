@@ -1,4 +1,3 @@
-
 //! Adds parametrization capabilty to `#[test]` via `#[args]` attribute macro.
 //!
 //! # To install via umbrella devbox crate
@@ -26,14 +25,18 @@
 //!
 //! [#\[args\]]: https://doc.rust-lang.org/devbox_test_args/attr.args.html
 
-use std::iter::FromIterator;
+#![allow(clippy::test_attr_in_doctest)]
+
+use std::{collections::HashSet, iter::FromIterator};
 use proc_macro::TokenStream;
-use proc_macro2::{Ident, Span};
+use proc_macro2::{Ident, Span, TokenTree};
 use proc_macro_error::{abort, emit_error, proc_macro_error};
 use quote::quote;
 use syn::{
-    parse_macro_input, Block, Expr, FnArg, ItemFn, LitStr, Local, Pat, Result, Stmt, Token,
-    parse::{Parse, ParseStream},
+    parse_macro_input, Attribute, Block, Expr, ExprLit, ExprRange, ExprUnary, FnArg, GenericParam,
+    ItemFn, Lit, LitBool, LitInt, LitStr, Local, Pat, RangeLimits, Result, ReturnType, Stmt, Token,
+    Type, UnOp,
+    parse::{Parse, ParseStream, Parser},
     punctuated::Punctuated,
     token::{Eq, Let, Semi},
 };
@@ -45,6 +48,13 @@ use syn::{
 /// A test function can have any number of parameters which can have anonymouse types that will
 /// be filled in by the attribute based it's arguments.
 ///
+/// Parameters can also have explicit types, like `code: u8` or `text: &str`, documenting them at
+/// the parameter site with case arguments coerced to them the same way as in a function call, and
+/// `impl Trait` types like `value: impl Into<i64>` which keep only the trait's methods available.
+///
+/// Parameters can use destructuring patterns like `(x, y): (i32, i32)` or `Point { x, y }: _`
+/// which are bound to case arguments the same way as in a `let` statement.
+///
 /// Make sure attribute is applied before the standard Rust `#[test]` attribute or you will
 /// get *functions used as tests can not have any arguments* error. You can also use [`test_args`]
 /// attribute instead which appends the `#[test]` automatically.
@@ -56,20 +66,183 @@ use syn::{
 /// Macro emits a new standard Rust test for each named argument set (also called a case) by
 /// suffixing function name with case name.
 ///
-/// Cases are seperated by `;` and need to have unique names for particular test function.
-/// Each case needs argument list seperated by `,` that consumes equal number of function parameters
-/// when generating the actual test function.
+/// Cases are seperated by `;` and need to have unique (case-sensitive) names for particular test
+/// function. Each case needs argument list seperated by `,` that consumes equal number of function
+/// parameters when generating the actual test function.
+///
+/// Empty cases from consecutive or trailing `;` are skipped, so large case lists can be grouped
+/// with blank lines and comments freely.
 ///
 /// To mark a case as one that should panic, add a suffix with a slice of expected message after `!`
 ///
-/// Syntax for a case is ```<case-name>: <arg1>, <arg2> ... <argN> [! "<message slice>"];```
+/// To apply attributes like `#[ignore]` or `#[cfg(...)]` to a single case only, put them in front
+/// of case arguments.
+///
+/// Syntax for a case is:
+/// ```txt
+/// <case-name>: [#[<attribute>]...] <arg1>, <arg2> ... <argN> [@ "<description>"] [! "<message slice>"];
+/// ```
+///
+/// Optional case description after `@` becomes part of the generated test name to make test output
+/// self-documenting. It is lowercased with runs of non-alphanumeric characters replaced by single
+/// `_`, so `case_a: 42 @ "Handles empty input";` produces test named like
+/// `parametrized_test_for__case_a__handles_empty_input`.
+///
+/// Duplicate case names are reported as compile errors pointing at the offending case:
+///
+/// ```rust,compile_fail
+/// # use devbox_test_args::args;
+/// #[args(
+///     char_a: 'a';
+///     char_a: 'b';
+/// )]
+/// #[test]
+/// fn parametrized_test_for(character:_) {
+///     assert!(character.is_alphabetic());
+/// }
+/// ```
+///
+/// Test functions can not be generic since each generated test needs concrete parameter types.
+/// Use anonymous `_` types instead and pick the concrete type with typed case arguments like
+/// `1i64`. Type or const parameters are reported as compile errors pointing at them:
+///
+/// ```rust,compile_fail
+/// # use devbox_test_args::args;
+/// #[args(
+///     case_a: 1i64;
+/// )]
+/// #[test]
+/// fn parametrized_test_for<T: Into<i64>>(value: T) {
+///     assert!(value.into() > 0);
+/// }
+/// ```
 ///
 /// # Cartesian product
 ///
 /// You can apply mutiple test macros to a single function with individual macro cases consuming
 /// only a subset of function parameters. This forms a cartesian product of cases from each macro
-/// instance. It is import that all cartesian products consume all parameters or you will end up
-/// with a test function with nonzero parameters which is not supported by Rust built in test macro.
+/// instance. It is import that all cartesian products consume all parameters, the last macro
+/// reports parameters left unbound by any of its cases as compile errors:
+///
+/// ```rust,compile_fail
+/// # use devbox_test_args::args;
+/// #[args(
+///     char_a: 97;
+/// )]
+/// #[args(
+///     offset_0: 0;
+/// )]
+/// #[test]
+/// fn parametrized_test_for(code:_, offset:_, letter:_) {
+///     assert_eq!(code + offset, letter as u8);
+/// }
+/// ```
+///
+/// # Named arguments
+///
+/// Instead of by position, case arguments can also bind parameters by their name using
+/// `<param-name> = <arg>` form, for example `char_a: letter = 'a', code = 97;`. Named arguments
+/// can be given in any order but can not be mixed with positional ones within the same case.
+///
+/// ```rust,compile_fail
+/// # use devbox_test_args::args;
+/// #[args(
+///     char_a: letter = 'a', 97;
+/// )]
+/// #[test]
+/// fn parametrized_test_for(code:_, letter:_) {
+///     assert_eq!(code, letter as u8);
+/// }
+/// ```
+///
+/// # Case lists
+///
+/// A case with a single bracketed list value expands into one test per list element suffixed with
+/// element's index. For example `primes: [2, 3, 5, 7];` produces tests `primes_0` to `primes_3`.
+/// Lists are not expanded when the parameter they bind is declared as an array or a slice.
+///
+/// Similarly, a case with a single integer range value like `sweep: 1..=5;` expands into one test
+/// per integer suffixed with the integer itself, producing tests `sweep_1` to `sweep_5`. Negative
+/// integers are suffixed with `neg` prefix like `sweep_neg1`. Both range bounds must be integer
+/// literals unless the parameter is declared as one of the range types.
+///
+/// ```rust,compile_fail
+/// # use devbox_test_args::args;
+/// #[args(
+///     sweep: 0.5..2.5;
+/// )]
+/// #[test]
+/// fn parametrized_test_for(value:_) {
+///     assert!(value > 0.0);
+/// }
+/// ```
+///
+/// # CSV cases
+///
+/// Cases can also be loaded from a CSV file using `csv = "<path>"` option with `path` relative to
+/// crate's manifest directory. Each row becomes a case with the case name in the first column and
+/// the remaining columns parsed as Rust expressions for case arguments, so cells like `97` or `'a'`
+/// work as expected. Empty lines and lines starting with `#` are skipped. Loaded cases are added
+/// after the ones given inline and are expanded the same way.
+///
+/// ```txt
+/// # tests/data/letters.csv
+/// char_a, 97, 'a'
+/// char_b, 98, 'b'
+/// ```
+///
+/// ```rust,ignore
+/// # use devbox_test_args::args;
+/// #[args(csv = "tests/data/letters.csv")]
+/// #[test]
+/// fn parametrized_test_for(code:_, letter:_) {
+///     assert_eq!(code, letter as u8);
+/// }
+/// ```
+///
+/// # Result return type
+///
+/// Test functions can return `Result` just like standard Rust tests do. Since Rust only allows
+/// `#[should_panic]` on tests returning `()`, cases expecting a panic are rejected for such
+/// functions at compile time:
+///
+/// ```rust,compile_fail
+/// # use devbox_test_args::args;
+/// #[args(
+///     zero: "0";
+///     word: "zero" ! "invalid digit";
+/// )]
+/// #[test]
+/// fn parametrized_test_for(number:_) -> Result<(), std::num::ParseIntError> {
+///     number.parse::<u32>()?;
+///     Ok(())
+/// }
+/// ```
+///
+/// # Case summary
+///
+/// Starting the attribute with `summary = true;` option prepends a doc comment to every generated
+/// test naming its source case and arguments, which makes `cargo expand` output of large case
+/// tables easier to follow. Stacked attributes each add their own line.
+///
+/// ```rust
+/// # use devbox_test_args::args;
+/// #[args(
+///     summary = true;
+///     char_a: 97, 'a';
+/// )]
+/// #[test]
+/// fn parametrized_test_for(code:_, letter:_) {
+///     assert_eq!(code, letter as u8);
+/// }
+/// ```
+///
+/// # Attributes
+///
+/// All other attributes of the test function are preserved on every generated test, including
+/// those placed above the macro like `#[cfg_attr(test, ignore)]`, and keep their original order
+/// across the whole cartesian product. Attributes emitted by the macro itself (`#[test]` and
+/// `#[should_panic]`) are always placed in front of them, preceded only by case attributes.
 ///
 /// # Example
 ///
@@ -109,6 +282,22 @@ pub fn args(attr: TokenStream, input: TokenStream) -> TokenStream {
 
 /// Same as [`args`] but applying standard Rust `#[test]` attribute automatically
 ///
+/// To apply a different test attribute, like the one of an async runtime, start the attribute
+/// with `runtime = <attribute-path>;` option. Async test functions are supported as long as the
+/// runtime attribute supports them. Option `attr = <attribute-path>;` is the same for attributes
+/// that are not runtimes, like the ones of benchmarks or custom test harnesses.
+///
+/// ```rust
+/// # use devbox_test_args::test_args;
+/// #[test_args(
+///     runtime = tokio::test;
+///     char_a: 97, 'a';
+/// )]
+/// async fn parametrized_test_for(code:_, letter:_) {
+///     assert_eq!(code, letter as u8);
+/// }
+/// ```
+///
 /// [`args`]: attr.args.html
 ///
 #[proc_macro_attribute]
@@ -117,48 +306,329 @@ pub fn test_args(attr: TokenStream, input: TokenStream) -> TokenStream {
     apply_test_args(attr, input, true)
 }
 
+/// Companion to [`args`] generating tests for all combinations of explicitly labeled dimensions
+///
+/// Each dimension has a label and a list of labeled values separated by `,` and consumes one
+/// function parameter in order, following the same parameter consumption rules as [`args`]
+/// including cartesian products with other macro instances. Generated test names join dimension
+/// and value labels making it clear which dimension each part of the name comes from.
+///
+/// Syntax for a dimension is:
+/// ```txt
+/// <dimension-name>: <label1> = <arg1>, <label2> = <arg2> ... <labelN> = <argN>;
+/// ```
+///
+/// # Example
+///
+/// ```rust
+/// # use devbox_test_args::matrix;
+/// #[matrix(
+///     code: a = 97, b = 98;
+///     offset: zero = 0, one = 1;
+/// )]
+/// #[test]
+/// fn parametrized_test_for(code:_, offset:_) {
+///     assert!(code + offset >= 97);
+/// }
+/// ```
+///
+/// Should produce:
+/// ```txt
+/// test parametrized_test_for__code_a__offset_zero ... ok
+/// test parametrized_test_for__code_a__offset_one ... ok
+/// test parametrized_test_for__code_b__offset_zero ... ok
+/// test parametrized_test_for__code_b__offset_one ... ok
+/// ```
+///
+/// [`args`]: attr.args.html
+///
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn matrix(attr: TokenStream, input: TokenStream) -> TokenStream {
+    let matrix = parse_macro_input!(attr as Matrix);
+    let input = parse_macro_input!(input as ItemFn);
+    emit_tests(&input, matrix.cases(), None, false).into()
+}
+
 //-- Implemenatation -------------------------------------------------------------------------------
 
 /// Main entry point for both macros
 fn apply_test_args(attr: TokenStream, input: TokenStream, append_test_attr: bool) -> TokenStream {
     let cases = parse_macro_input!(attr as Cases);
     let input = parse_macro_input!(input as ItemFn);
+    let runtime = match (append_test_attr, cases.runtime) {
+        (true, runtime) => Some(runtime.unwrap_or_else(|| syn::parse_quote!(test))),
+        (false, Some(runtime)) => abort!(
+            runtime,
+            "Devbox: Options 'runtime' and 'attr' are only supported by '#[test_args]' attribute"
+        ),
+        (false, None) => None,
+    };
+    let mut all: Vec<Case> = cases.cases.into_iter().collect();
+    let mut output = quote!{};
+    if let Some(csv) = &cases.csv {
+        let (file, csv_cases) = csv_cases(csv);
+        all.extend(csv_cases);
+        output.extend(quote!{ const _: &str = include_str!(#file); });
+    }
+    let expanded = expand_lists(&input, all);
+    output.extend(emit_tests(&input, expanded, runtime.as_ref(), cases.summary));
+    output.into()
+}
+
+/// First type or const generic parameter of the function which tests can not have, unlike lifetimes
+fn generic_param(func: &ItemFn) -> Option<&GenericParam> {
+    func.sig.generics.params.iter().find(|param| !matches!(param, GenericParam::Lifetime(_)))
+}
+
+/// First case with the same (case-sensitive) name as one of the cases before it
+fn duplicate_case(cases: &[Case]) -> Option<&Case> {
+    let mut names = HashSet::new();
+    cases.iter().find(|case| !names.insert(case.name()))
+}
+
+/// Emits one test function per case or the input function itself when there are no cases
+fn emit_tests(input: &ItemFn, cases: Vec<Case>, runtime: Option<&syn::Path>, summary: bool)
+    -> proc_macro2::TokenStream
+{
+    if let Some(param) = generic_param(input) {
+        abort!(
+            param,
+            "Devbox: Parametrized test function '{}' can not be generic, use anonymous '_' \
+            parameter types with typed case arguments like '1i64' instead",
+            input.sig.ident
+        );
+    }
 
-    if cases.0.len() == 0 {
-        let test = test_attribute(&input, append_test_attr);
+    if let Some(case) = duplicate_case(&cases) {
+        abort!(case.ident, "Devbox: duplicate case name '{}'", case.name());
+    }
+
+    if cases.is_empty() {
+        let test = test_attribute(input, runtime);
         return quote!{
             #test
             #input
-        }.into();
+        };
     }
 
     let mut output = quote!{};
-    for case in cases.0 {
+    for case in cases {
+        let summary = if summary { Some(case_summary(&case)) } else { None };
+        let summary = summary.map(|doc| quote!{ #[doc = #doc] });
+        if let (Some(panics), ReturnType::Type(_, ty)) = (&case.panics, &input.sig.output) {
+            if !is_unit_type(ty) {
+                emit_error!(
+                    panics,
+                    "Devbox: Test case '{}' expects a panic but function '{}' returns a value which \
+                    is not supported by '#[should_panic]'",
+                    case.ident, input.sig.ident
+                );
+            }
+        }
+
         let should_panic = case.panics.clone().map(|e| quote!{ #[should_panic(expected = #e)] });
-        let func = make_case_function(&input, case);
-        let test = test_attribute(&func, append_test_attr);
+        let attrs = case.attrs.clone();
+        let func = make_case_function(input, case);
+        if !func.sig.inputs.is_empty() && !has_case_attribute(&func) {
+            let unbound: Vec<String> = func.sig.inputs.iter().map(param_label).collect();
+            let params = if unbound.len() == 1 { "parameter" } else { "parameters" };
+            abort!(
+                func.sig.inputs,
+                "Devbox: {} {} left unbound after applying all cases to function '{}': {}",
+                unbound.len(), params, original_name(&input.sig.ident), unbound.join(", ")
+            );
+        }
+        let test = test_attribute(&func, runtime);
 
         output.extend(quote!{
+            #summary
+            #(#attrs)*
             #test
             #should_panic
             #func
         });
     }
-    output.into()
+    output
 }
 
-/// Checks if the test function already has the `#[test]` attribute applied
-fn test_attribute(func: &ItemFn, add_if_needed: bool) -> Option<proc_macro2::TokenStream> {
-    if func.sig.inputs.len() > 0 ||
-       func.attrs.iter().any(|a| a.path.segments.last().map_or(false, |seg|seg.ident=="test"))
+/// Checks if the test function already has the `#[test]` or `runtime` test attribute applied
+/// and returns the `runtime` one to be added if not
+fn test_attribute(func: &ItemFn, runtime: Option<&syn::Path>) -> Option<proc_macro2::TokenStream> {
+    let runtime_name = runtime.and_then(|path| path.segments.last()).map(|seg| &seg.ident);
+    let is_test = |ident: &Ident| ident == "test" || Some(ident) == runtime_name;
+    if !func.sig.inputs.is_empty() ||
+       func.attrs.iter().any(|a| a.path.segments.last().is_some_and(|seg| is_test(&seg.ident)))
     {
         return None;
     }
 
-    if add_if_needed {
-        Some(quote!{ #[test] })
-    } else {
-        abort!(func, "Devbox: Function '{}' is missing '#[test]' attribute", func.sig.ident);
+    match runtime {
+        Some(runtime) => Some(quote!{ #[#runtime] }),
+        None => abort!(func, "Devbox: Function '{}' is missing '#[test]' attribute", func.sig.ident),
+    }
+}
+
+/// Reads cases from CSV file at `path` relative to the crate's manifest directory returning them
+/// with the file's full path which is included in the output to rebuild tests when it changes
+fn csv_cases(path: &LitStr) -> (String, Vec<Case>) {
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let file = std::path::Path::new(&dir).join(path.value());
+    let text = match std::fs::read_to_string(&file) {
+        Ok(text) => text,
+        Err(err) => abort!(path, "Devbox: Reading cases from {} FAILED: {}", file.display(), err),
+    };
+
+    let cases = text.lines().enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(index, line)| match csv_case(line, path.span()) {
+            Ok(case) => case,
+            Err(err) => abort!(
+                path, "Devbox: Malformed case at {}:{}: {}", file.display(), index + 1, err
+            ),
+        })
+        .collect();
+    (file.display().to_string(), cases)
+}
+
+/// Parses CSV `line` with case name in the first column and case arguments in the others
+fn csv_case(line: &str, span: Span) -> Result<Case> {
+    let (name, values) = line.split_once(',').unwrap_or((line, ""));
+    let mut ident: Ident = syn::parse_str(name.trim())?;
+    ident.set_span(span);
+
+    let values = Punctuated::<Expr, Token![,]>::parse_terminated.parse_str(values)?;
+    if values.is_empty() {
+        return Err(syn::Error::new(span, format!("Test case '{}' has no arguments", ident)));
+    }
+
+    Ok(Case {
+        ident,
+        attrs: vec![],
+        values: values.into_iter().collect(),
+        description: None,
+        panics: None,
+    })
+}
+
+/// Describes case name, arguments and expected panic for generated function's doc comment
+fn case_summary(case: &Case) -> String {
+    let values: Vec<String> = case.values.iter().map(|expr| quote!{ #expr }.to_string()).collect();
+    let mut summary = format!("Test case `{}`: {}", case.ident, values.join(", "));
+    if let Some(panics) = &case.panics {
+        summary.push_str(&format!(" (panics with \"{}\")", panics.value()));
+    }
+    summary
+}
+
+/// Replaces each case having a single list or integer range value with one case per element
+/// unless the value is bound to a parameter of an array, slice or range type respectively
+fn expand_lists(input: &ItemFn, cases: Vec<Case>) -> Vec<Case> {
+    let param_type = match input.sig.inputs.first() {
+        Some(FnArg::Typed(arg)) => Some(&*arg.ty),
+        _ => None,
+    };
+    let array_param = param_type.is_some_and(is_array_type);
+    let range_param = param_type.is_some_and(is_range_type);
+
+    cases.into_iter().flat_map(|case| match case.values.as_slice() {
+        [Expr::Array(list)] if !array_param => list.elems.iter().enumerate().map(|(i, elem)| Case {
+            ident: Ident::new(&format!("{}_{}", case.ident, i), case.ident.span()),
+            attrs: case.attrs.clone(),
+            values: vec![elem.clone()],
+            description: case.description.clone(),
+            panics: case.panics.clone(),
+        }).collect(),
+        [Expr::Range(range)] if !range_param => expand_range(&case, range).into_iter().map(|lit| Case {
+            ident: Ident::new(&format!("{}_{}", case.ident, lit.1), case.ident.span()),
+            attrs: case.attrs.clone(),
+            values: vec![lit.0],
+            description: case.description.clone(),
+            panics: case.panics.clone(),
+        }).collect(),
+        _ => vec![case],
+    }).collect()
+}
+
+/// Integer values of range expression with their case name suffixes
+fn expand_range(case: &Case, range: &ExprRange) -> Vec<(Expr, String)> {
+    let (from, to) = match (
+        range.from.as_deref().and_then(range_bound), range.to.as_deref().and_then(range_bound)
+    ) {
+        (Some(from), Some(to)) => (from, to),
+        _ => abort!(
+            range,
+            "Devbox: Test case '{}' range must have integer literal bounds",
+            case.ident
+        ),
+    };
+
+    let to = match range.limits {
+        RangeLimits::Closed(_) => to.0,
+        RangeLimits::HalfOpen(_) => to.0 - 1,
+    };
+    if from.0 > to {
+        abort!(range, "Devbox: Test case '{}' range is empty", case.ident);
+    }
+
+    let suffix = from.1;
+    (from.0..=to).map(|value| {
+        let lit = LitInt::new(&format!("{}{}", value.abs(), suffix), Span::call_site());
+        let name = if value < 0 { format!("neg{}", -value) } else { value.to_string() };
+        let expr = if value < 0 { syn::parse_quote!(-#lit) } else { syn::parse_quote!(#lit) };
+        (expr, name)
+    }).collect()
+}
+
+/// Value and type suffix of integer literal range bound, possibly negated
+fn range_bound(expr: &Expr) -> Option<(i128, String)> {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Int(lit), .. }) =>
+            lit.base10_parse().ok().map(|value| (value, lit.suffix().to_owned())),
+        Expr::Unary(ExprUnary { op: UnOp::Neg(_), expr, .. }) =>
+            range_bound(expr).map(|(value, suffix)| (-value, suffix)),
+        Expr::Paren(paren) => range_bound(&paren.expr),
+        _ => None,
+    }
+}
+
+/// Checks if type is one of standard range types
+fn is_range_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path.path.segments.last()
+            .is_some_and(|seg| seg.ident.to_string().starts_with("Range")),
+        Type::Reference(reference) => is_range_type(&reference.elem),
+        _ => false,
+    }
+}
+
+/// Checks if type is an array or a slice or a reference to one
+fn is_array_type(ty: &Type) -> bool {
+    match ty {
+        Type::Array(_) | Type::Slice(_) => true,
+        Type::Reference(reference) => is_array_type(&reference.elem),
+        _ => false,
+    }
+}
+
+/// Checks if type contains `impl Trait` anywhere, like `impl Into<i64>` or `Vec<impl Debug>`
+fn has_impl_trait(ty: &Type) -> bool {
+    fn contains_impl(tokens: proc_macro2::TokenStream) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => ident == "impl",
+            TokenTree::Group(group) => contains_impl(group.stream()),
+            _ => false,
+        })
+    }
+    contains_impl(quote!{ #ty })
+}
+
+/// Checks if type is the unit type `()`
+fn is_unit_type(ty: &Type) -> bool {
+    match ty {
+        Type::Tuple(tuple) => tuple.elems.is_empty(),
+        Type::Paren(paren) => is_unit_type(&paren.elem),
+        _ => false,
     }
 }
 
@@ -173,31 +643,121 @@ fn make_case_function(input: &ItemFn, case: Case) -> ItemFn {
     }
 
     let mut func = input.clone();
-    let name = format!("{}__{}", func.sig.ident, case.ident.to_string());
+    let name = format!("{}__{}", func.sig.ident, case.name());
     func.sig.ident = Ident::new(name.as_ref(), Span::call_site());
 
-    let inputs = func.sig.inputs.clone();
-    let mut args = inputs.iter().map(|t|t.clone());
-    for expr in case.values {
-        if let Some(arg) = args.next() {
-            insert_param(&mut func.block, arg, expr);
+    let mut args: Vec<FnArg> = func.sig.inputs.iter().cloned().collect();
+    let named = case.values.iter().filter(|expr| named_value(expr).is_some()).count();
+    if named == 0 {
+        for expr in case.values {
+            if !args.is_empty() {
+                insert_param(&mut func.block, args.remove(0), Box::new(expr));
+            }
         }
+    } else if named == case.values.len() {
+        for expr in &case.values {
+            let (name, value) = named_value(expr).unwrap();
+            match args.iter().position(|arg| param_name(arg) == Some(name)) {
+                Some(index) => insert_param(&mut func.block, args.remove(index), Box::new(value.clone())),
+                None => emit_error!(
+                    expr,
+                    "Devbox: Test case '{}' names unknown or already bound parameter '{}'",
+                    case.ident, name
+                ),
+            }
+        }
+    } else {
+        emit_error!(
+            case.ident,
+            "Devbox: Test case '{}' mixes named and positional arguments",
+            case.ident
+        );
     }
 
     func.sig.inputs = syn::punctuated::Punctuated::from_iter(args);
     func
 }
 
+/// Splits `name = value` case argument into parameter name and value expression
+fn named_value(expr: &Expr) -> Option<(&Ident, &Expr)> {
+    match expr {
+        Expr::Assign(assign) => match &*assign.left {
+            Expr::Path(path) => path.path.get_ident().map(|name| (name, &*assign.right)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Name of function parameter if it is a simple identifier
+fn param_name(arg: &FnArg) -> Option<&Ident> {
+    match arg {
+        FnArg::Typed(arg) => match &*arg.pat {
+            Pat::Ident(pat) => Some(&pat.ident),
+            _ => None,
+        },
+        FnArg::Receiver(_) => None,
+    }
+}
+
+/// Name of the test function as written by the user, without case name suffixes appended to it by
+/// the outer macros, like `parametrized_test_for` for `parametrized_test_for__char_a`
+fn original_name(ident: &Ident) -> String {
+    let name = ident.to_string();
+    match name.get(1..).and_then(|rest| rest.find("__")) {
+        Some(index) => name[..index + 1].to_owned(),
+        None => name,
+    }
+}
+
+/// Parameter pattern as written in the function signature, like `code` or `(x, y)`
+fn param_label(arg: &FnArg) -> String {
+    match arg {
+        FnArg::Typed(arg) => {
+            let pat = &arg.pat;
+            quote!{ #pat }.to_string()
+        },
+        FnArg::Receiver(_) => "self".to_owned(),
+    }
+}
+
+/// Checks if the function has another parametrization attribute of this crate still to be applied
+fn has_case_attribute(func: &ItemFn) -> bool {
+    func.attrs.iter().any(|attr| attr.path.segments.last().is_some_and(|seg| {
+        seg.ident == "args" || seg.ident == "test_args" || seg.ident == "matrix"
+    }))
+}
+
 /// Replaces one function parameter with one attribute case argument
+///
+/// Parameter becomes a `let` statement like `let (x, y): (i32, i32) = <case argument>;` so any
+/// pattern, including tuple and struct destructuring, is bound the same way as in the function
+/// signature. Parameter attributes are moved onto the statement since patterns can not have them.
+///
+/// Types with `impl Trait` are not allowed in `let` statements, so those are bound through an
+/// identity function with the parameter type instead, applying the same trait bounds.
 fn insert_param(block: &mut Box<Block>, arg: FnArg, init:Box<Expr>){
     match arg {
-        FnArg::Typed(arg) => block.stmts.insert(0, Stmt::Local(Local {
-            attrs: vec![],
-            let_token: Let { span: Span::call_site() },
-            pat: Pat::Type(arg),
-            init: Some((Eq{ spans: [Span::call_site()] }, init)),
-            semi_token: Semi { spans: [Span::call_site()] },
-        })),
+        FnArg::Typed(mut arg) => {
+            let attrs = std::mem::take(&mut arg.attrs);
+            let (pat, init) = if has_impl_trait(&arg.ty) {
+                let ty = &arg.ty;
+                let bound = syn::parse_quote!{{
+                    fn param(value: #ty) -> #ty { value }
+                    param(#init)
+                }};
+                (*arg.pat, Box::new(bound))
+            } else {
+                (Pat::Type(arg), init)
+            };
+            block.stmts.insert(0, Stmt::Local(Local {
+                attrs,
+                let_token: Let { span: Span::call_site() },
+                pat,
+                init: Some((Eq{ spans: [Span::call_site()] }, init)),
+                semi_token: Semi { spans: [Span::call_site()] },
+            }))
+        },
         FnArg::Receiver(_) => emit_error!(
             arg,
             "Devbox: Parametrized test applied to non-associated function"
@@ -209,25 +769,60 @@ fn insert_param(block: &mut Box<Block>, arg: FnArg, init:Box<Expr>){
 
 struct Case {
     pub ident: Ident,
-    pub colon: Token![:],
-    pub values: Vec<Box<Expr>>,
+    pub attrs: Vec<Attribute>,
+    pub values: Vec<Expr>,
+    pub description: Option<LitStr>,
     pub panics: Option<LitStr>,
 }
 
+impl Case {
+    /// Case name suffixed with description slug if there is one
+    fn name(&self) -> String {
+        match self.description.as_ref().map(|desc| slugify(&desc.value())) {
+            Some(slug) if !slug.is_empty() => format!("{}__{}", self.ident, slug),
+            _ => self.ident.to_string(),
+        }
+    }
+}
+
+/// Lowercases the text replacing each run of non-alphanumeric characters with a single `_`
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('_') {
+            slug.push('_');
+        }
+    }
+    slug.trim_end_matches('_').to_owned()
+}
+
 impl Parse for Case {
     fn parse(input: ParseStream) -> Result<Self> {
         Ok(Case {
             ident: input.parse()?,
-            colon: input.parse()?,
+            attrs: {
+                input.parse::<Token![:]>()?;
+                input.call(Attribute::parse_outer)?
+            },
             values: {
-                let mut result = vec![Box::new(input.parse()?)];
+                let mut result = vec![input.parse()?];
                 let mut more: Option<Token![,]> = input.parse()?;
                 while more.is_some() {
-                    result.push(Box::new(input.parse()?));
+                    result.push(input.parse()?);
                     more = input.parse()?;
                 }
                 result
             },
+            description: {
+                let at: Option<Token![@]> = input.parse()?;
+                if at.is_some() {
+                    Some(input.parse()?)
+                } else {
+                    None
+                }
+            },
             panics: {
                 let excl: Option<Token![!]> = input.parse()?;
                 if excl.is_some() {
@@ -240,10 +835,271 @@ impl Parse for Case {
     }
 }
 
-struct Cases(Punctuated<Case, Token![;]>);
+struct Cases {
+    pub summary: bool,
+    pub runtime: Option<syn::Path>,
+    pub csv: Option<LitStr>,
+    pub cases: Vec<Case>,
+}
 
 impl Parse for Cases {
     fn parse(input: ParseStream) -> Result<Self> {
-        Ok(Cases(input.parse_terminated(Case::parse)?))
+        let mut summary = false;
+        let mut runtime = None;
+        let mut csv = None;
+        while skip_separators(input)? && input.peek(syn::Ident) && input.peek2(Token![=]) {
+            let option: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            match option.to_string().as_str() {
+                "summary" => summary = input.parse::<LitBool>()?.value,
+                "runtime" | "attr" if runtime.is_some() => return Err(syn::Error::new(
+                    option.span(),
+                    "Devbox: Test attribute already set by 'runtime' or 'attr' option"
+                )),
+                "runtime" | "attr" => runtime = Some(input.parse()?),
+                "csv" => csv = Some(input.parse()?),
+                _ => return Err(syn::Error::new(
+                    option.span(), format!("Devbox: Unknown option '{}'", option)
+                )),
+            }
+            if !input.is_empty() {
+                input.parse::<Token![;]>()?;
+            }
+        }
+
+        Ok(Cases {
+            summary,
+            runtime,
+            csv,
+            cases: parse_separated(input)?,
+        })
+    }
+}
+
+struct Dimension {
+    pub ident: Ident,
+    pub values: Punctuated<(Ident, Expr), Token![,]>,
+}
+
+impl Parse for Dimension {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Dimension {
+            ident: input.parse()?,
+            values: {
+                input.parse::<Token![:]>()?;
+                Punctuated::parse_separated_nonempty_with(input, |input| {
+                    let label = input.parse()?;
+                    input.parse::<Token![=]>()?;
+                    Ok((label, input.parse()?))
+                })?
+            },
+        })
+    }
+}
+
+struct Matrix(Vec<Dimension>);
+
+impl Matrix {
+    /// Cartesian product of all dimension values as cases named by dimension and value labels
+    fn cases(&self) -> Vec<Case> {
+        self.0.iter().fold(Vec::new(), |cases, dim| {
+            let labeled = dim.values.iter().map(|(label, value)| {
+                (format!("{}_{}", dim.ident, label), value.clone())
+            });
+            if cases.is_empty() {
+                return labeled.map(|(name, value)| Case {
+                    ident: Ident::new(&name, dim.ident.span()),
+                    attrs: vec![],
+                    values: vec![value],
+                    description: None,
+                    panics: None,
+                }).collect();
+            }
+
+            let labeled: Vec<_> = labeled.collect();
+            cases.iter().flat_map(|case| labeled.iter().map(move |(name, value)| Case {
+                ident: Ident::new(&format!("{}__{}", case.ident, name), case.ident.span()),
+                attrs: vec![],
+                values: case.values.iter().cloned().chain(Some(value.clone())).collect(),
+                description: None,
+                panics: None,
+            })).collect()
+        })
+    }
+}
+
+impl Parse for Matrix {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Matrix(parse_separated(input)?))
+    }
+}
+
+/// Parses entries separated by `;` skipping empty ones, like consecutive or trailing separators
+fn parse_separated<T: Parse>(input: ParseStream) -> Result<Vec<T>> {
+    let mut entries = vec![];
+    while skip_separators(input)? {
+        entries.push(input.parse()?);
+        if !input.is_empty() {
+            input.parse::<Token![;]>()?;
+        }
+    }
+    Ok(entries)
+}
+
+/// Skips any number of `;` separators returning true if there is more input after them
+fn skip_separators(input: ParseStream) -> Result<bool> {
+    while input.peek(Token![;]) {
+        input.parse::<Token![;]>()?;
+    }
+    Ok(!input.is_empty())
+}
+
+//-- Tests -----------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn original_name_strips_case_suffixes() {
+        let name = |ident| original_name(&Ident::new(ident, Span::call_site()));
+        assert_eq!(name("parametrized_test_for__char_a__offset_0"), "parametrized_test_for");
+        assert_eq!(name("parametrized_test_for"), "parametrized_test_for");
+        assert_eq!(name("__private__char_a"), "__private");
+    }
+
+    #[test]
+    fn case_summary_names_case_and_arguments() {
+        let case: Case = syn::parse_str("char_a: 97, 'a'").unwrap();
+        assert_eq!(case_summary(&case), "Test case `char_a`: 97, 'a'");
+    }
+
+    #[test]
+    fn case_summary_names_expected_panic() {
+        let case: Case = syn::parse_str("char_b: #[ignore] 98, 'c' ! \"wrong char\"").unwrap();
+        assert_eq!(case_summary(&case), "Test case `char_b`: 98, 'c' (panics with \"wrong char\")");
+    }
+
+    #[test]
+    fn case_name_with_description() {
+        let case: Case = syn::parse_str("case_a: 42 @ \" Handles -- EMPTY input!\" ! \"msg\"").unwrap();
+        assert_eq!(case.name(), "case_a__handles_empty_input");
+        assert_eq!(case.panics.unwrap().value(), "msg");
+
+        let case: Case = syn::parse_str("case_a: 42 @ \"???\"").unwrap();
+        assert_eq!(case.name(), "case_a");
+    }
+
+    #[test]
+    fn generic_param_of_function() {
+        let func: ItemFn = syn::parse_str("fn t<'a, T: Into<i64>>(v: &'a T) {}").unwrap();
+        let param = generic_param(&func);
+        assert_eq!(quote!{ #param }.to_string(), "T : Into < i64 >");
+
+        let func: ItemFn = syn::parse_str("fn t<'a>(v: &'a str) {}").unwrap();
+        assert!(generic_param(&func).is_none());
+    }
+
+    #[test]
+    fn impl_trait_types() {
+        let has_impl = |ty: &str| has_impl_trait(&syn::parse_str(ty).unwrap());
+        assert!(has_impl("impl Into<i64>"));
+        assert!(has_impl("Vec<impl std::fmt::Debug>"));
+        assert!(has_impl("&(u8, impl Fn(u8) -> char)"));
+        assert!(!has_impl("Box<dyn Fn(u8) -> char>"));
+        assert!(!has_impl("_"));
+    }
+
+    #[test]
+    fn case_attribute_of_function() {
+        let func: ItemFn = syn::parse_str("#[args(a: 1;)] #[test] fn t(x:_) {}").unwrap();
+        assert!(has_case_attribute(&func));
+
+        let func: ItemFn = syn::parse_str("#[test] fn t((x, y): (u8, u8)) {}").unwrap();
+        assert!(!has_case_attribute(&func));
+        assert_eq!(param_label(&func.sig.inputs[0]), "(x , y)");
+    }
+
+    #[test]
+    fn duplicate_case_names() {
+        let cases: Cases = syn::parse_str("a: 1; A: 2; b: 3;").unwrap();
+        assert!(duplicate_case(&cases.cases).is_none());
+
+        let cases: Cases = syn::parse_str("a: 1; b: 2; a: 3;").unwrap();
+        let values = &duplicate_case(&cases.cases).unwrap().values;
+        assert_eq!(quote!{ #(#values),* }.to_string(), "3");
+    }
+
+    #[test]
+    fn csv_case_row() {
+        let case = csv_case("char_a, 97, 'a', \"x, y\"", Span::call_site()).unwrap();
+        assert_eq!(case.ident.to_string(), "char_a");
+        let values = &case.values;
+        assert_eq!(quote!{ #(#values),* }.to_string(), "97 , 'a' , \"x, y\"");
+
+        assert!(csv_case("char a, 97", Span::call_site()).is_err());
+        assert!(csv_case("char_a, 97 +", Span::call_site()).is_err());
+        assert!(csv_case("char_a", Span::call_site()).is_err());
+    }
+
+    #[test]
+    fn cases_empty_separators() {
+        let cases: Cases = syn::parse_str("a: 1;; b: 2;").unwrap();
+        let names: Vec<String> = cases.cases.iter().map(|case| case.ident.to_string()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+
+        let cases: Cases = syn::parse_str("; summary = true;; attr = bench;; a: 1;;").unwrap();
+        assert!(cases.summary && cases.runtime.is_some());
+        assert_eq!(cases.cases.len(), 1);
+
+        let matrix: Matrix = syn::parse_str("dim: a = 1;; other: x = 3;;").unwrap();
+        assert_eq!(matrix.cases().len(), 1);
+        assert!(syn::parse_str::<Cases>(";;").unwrap().cases.is_empty());
+    }
+
+    #[test]
+    fn cases_csv_option() {
+        let cases: Cases = syn::parse_str("csv = \"tests/data/cases.csv\"").unwrap();
+        assert_eq!(cases.csv.unwrap().value(), "tests/data/cases.csv");
+        assert!(cases.cases.is_empty());
+    }
+
+    #[test]
+    fn matrix_cases() {
+        let matrix: Matrix = syn::parse_str("dim: a = 1, b = 2; other: x = 3, y = 4;").unwrap();
+        let names: Vec<String> = matrix.cases().iter().map(|case| case.ident.to_string()).collect();
+        assert_eq!(names, vec!["dim_a__other_x", "dim_a__other_y", "dim_b__other_x", "dim_b__other_y"]);
+
+        let values = &matrix.cases()[2].values;
+        assert_eq!(quote!{ #(#values),* }.to_string(), "2 , 3");
+    }
+
+    #[test]
+    fn cases_summary_option() {
+        let cases: Cases = syn::parse_str("summary = true; char_a: 97; char_b: 98;").unwrap();
+        assert!(cases.summary);
+        assert_eq!(cases.cases.len(), 2);
+
+        let cases: Cases = syn::parse_str("char_a: 97;").unwrap();
+        assert!(!cases.summary);
+        assert!(cases.runtime.is_none());
+    }
+
+    #[test]
+    fn cases_attr_option() {
+        let cases: Cases = syn::parse_str("attr = bench; a: 1;").unwrap();
+        let attr = cases.runtime.unwrap();
+        assert_eq!(quote!{ #attr }.to_string(), "bench");
+
+        assert!(syn::parse_str::<Cases>("attr = bench; runtime = tokio::test; a: 1;").is_err());
+    }
+
+    #[test]
+    fn cases_runtime_option() {
+        let cases: Cases = syn::parse_str("runtime = tokio::test; summary = true; a: 1;").unwrap();
+        let runtime = cases.runtime.unwrap();
+        assert_eq!(quote!{ #runtime }.to_string(), "tokio :: test");
+        assert!(cases.summary);
+        assert_eq!(cases.cases.len(), 1);
     }
 }
\ No newline at end of file
@@ -1,4 +1,6 @@
-use devbox_test_args::{args, test_args};
+#![allow(clippy::assertions_on_constants)]
+
+use devbox_test_args::{args, matrix, test_args};
 
 //-- #[args] ---------------------------------------------------------------------------------------
 
@@ -21,6 +23,331 @@ fn args_cartesic(code:_, letter:_, offset:_) {
     assert_eq!(code + offset, letter as u8, "Letter code incorrect");
 }
 
+#[cfg_attr(test, should_panic(expected = "outer attribute"))]
+#[args(
+    char_a: 'a';
+    char_b: 'b';
+)]
+#[test]
+fn args_outer_attribute(letter:_) {
+    panic!("outer attribute preserved for '{}'", letter);
+}
+
+#[args(
+    char_a: 'a';
+    char_b: 'b';
+)]
+#[cfg_attr(test, should_panic(expected = "inner attribute"))]
+#[args(
+    offset_0: 0;
+    offset_1: 1;
+)]
+#[test]
+fn args_inner_attribute_cartesic(letter:_, offset:_) {
+    panic!("inner attribute preserved for '{}' {}", letter, offset);
+}
+
+// Fails when run if the `ignore` attribute gets lost for any of the cases
+#[cfg_attr(test, ignore)]
+#[args(
+    char_a: 'a';
+    char_b: 'b';
+)]
+#[args(
+    offset_0: 0;
+    offset_1: 1;
+)]
+#[test]
+fn args_outer_ignore_cartesic(letter:_, offset:_) {
+    panic!("ignored case '{}' {} was run", letter, offset);
+}
+
+#[args(
+    primes: [2, 3, 5, 7];
+)]
+#[test]
+fn args_list(prime:_) {
+    assert!([2, 3, 5, 7].contains(&prime));
+    assert!((2..prime).all(|d| prime % d != 0), "{} is not a prime", prime);
+}
+
+#[test]
+fn args_list_names() {
+    args_list__primes_0();
+    args_list__primes_1();
+    args_list__primes_2();
+    args_list__primes_3();
+}
+
+#[args(
+    pair: [1, 2];
+)]
+#[test]
+fn args_list_array_param(pair: [i32; 2]) {
+    assert_eq!([1, 2], pair);
+}
+
+#[args(
+    square: [(1, 1), (2, 4), (3, 9)];
+)]
+#[args(
+    offset_0: 0;
+    offset_1: 1 ! "not a square";
+)]
+#[test]
+fn args_list_cartesic(square:_, offset: i32) {
+    assert_eq!(square.0 * square.0, square.1 + offset, "not a square");
+}
+
+#[args(
+    plain: 1;
+    ignored: #[ignore] 2;
+    removed: #[cfg(any())] 3;
+    attrs: #[cfg(all())] #[ignore = "not ready"] 4;
+)]
+#[test]
+fn args_case_attributes(value:_) {
+    assert_eq!(1, value, "only the plain case should run");
+}
+
+#[args(
+    sweep: 1..=5;
+    upto: 0..3;
+    negative: -2..0;
+)]
+#[test]
+fn args_range(value:_) {
+    assert!((-2..=5).contains(&value));
+}
+
+#[args(
+    typed: 250u8..=255u8;
+)]
+#[test]
+fn args_range_typed(value:_) {
+    assert!(value >= 250);
+}
+
+#[test]
+fn args_range_names() {
+    args_range__sweep_1();
+    args_range__sweep_5();
+    args_range__upto_2();
+    args_range__negative_neg2();
+    args_range__negative_neg1();
+    args_range_typed__typed_255();
+}
+
+#[args(
+    short: 1..3;
+)]
+#[test]
+fn args_range_param(range: std::ops::Range<i32>) {
+    assert_eq!(2, range.len());
+}
+
+#[args(
+    sweep: 1..=2;
+)]
+#[args(
+    offset_0: 0;
+    offset_1: 1;
+)]
+#[test]
+fn args_range_cartesic(value:_, offset: i32) {
+    assert!(value + offset <= 3);
+}
+
+#[args(
+    char_a: 97, 'a', "a", &[97], Box::new(|code| code as char), Some(b"a");
+    char_b: 98, 'b', "b", &[98, 98], Box::new(|_| 'b'), None;
+)]
+#[test]
+fn args_explicit_types(
+    code: u8,
+    letter: char,
+    text: &str,
+    codes: &[u8],
+    convert: Box<dyn Fn(u8) -> char>,
+    bytes: Option<&[u8]>,
+) {
+    assert_eq!(letter, convert(code));
+    assert_eq!(text, letter.to_string());
+    assert!(codes.iter().all(|c| *c == code));
+    assert!(bytes.is_none_or(|bytes| bytes == text.as_bytes()));
+}
+
+#[args(
+    char_a: 97, 'a', "a", &[97], Box::new(|code: u8| code as char) as Box<dyn Fn(u8) -> char>;
+)]
+#[test]
+fn args_mixed_types(code: u8, letter:_, text: &'static str, codes:_, convert:_) {
+    let codes: &[u8] = codes;
+    assert_eq!(letter, convert(code));
+    assert_eq!(text, letter.to_string());
+    assert_eq!(&[code], codes);
+}
+
+#[args(
+    int: 1i32, |code| code as char, vec![1u8];
+    byte: 1u8, |_| '\u{1}', vec![1u8, 1u8];
+)]
+#[test]
+fn args_impl_trait_types(
+    value: impl Into<i64>,
+    convert: impl Fn(u8) -> char,
+    #[allow(unused_mut)] mut codes: Vec<impl Into<u32> + Copy>,
+) {
+    assert_eq!(1, value.into());
+    assert_eq!('\u{1}', convert(1));
+    assert!(codes.iter().all(|code| (*code).into() == 1));
+}
+
+#[args(
+    // Lowercase letters
+    char_a: 97, 'a';;
+    char_b: 98, 'b';
+
+    // Uppercase letters
+    char_upper_a: 65, 'A';
+    ;
+)]
+#[test]
+fn args_separators(code:_, letter:_) {
+    assert_eq!(code, letter as u8, "Letter code incorrect");
+}
+
+#[args(
+    origin: (0, 0), 0;
+    diagonal: (3, 3), 6;
+)]
+#[test]
+fn args_destructuring(#[allow(unused_mut)] (mut x, y): (i32, i32), sum: i32) {
+    assert_eq!(sum, x + y);
+}
+
+#[args(
+    pair: (1, 'a');
+    pairs: [(2, 'b'), (3, 'c')];
+)]
+#[test]
+fn args_destructuring_anonymous((code, letter):_) {
+    assert_eq!(letter as i32 - 'a' as i32 + 1, code);
+}
+
+struct Point { x: i32, y: i32 }
+
+#[args(
+    point: Point { x: 1, y: 2 };
+)]
+#[test]
+fn args_destructuring_struct(Point { x, y }: Point) {
+    assert_eq!(y, x * 2);
+}
+
+#[args(
+    case_a: "" @ "handles empty input";
+    case_b: "x" @ "Handles ONE char, too!" ! "too long";
+    case_c: ["ab", "abc"] @ "lists";
+)]
+#[test]
+fn args_description(input: &str) {
+    assert!(input.len() <= 3, "too long");
+    assert!(!input.starts_with('x'), "too long");
+}
+
+#[test]
+fn args_description_names() {
+    args_description__case_a__handles_empty_input();
+    args_description__case_c_0__lists();
+    args_description__case_c_1__lists();
+}
+
+#[args(csv = "tests/data/cases.csv")]
+#[test]
+fn args_csv(code:_, letter:_) {
+    assert_eq!(code, letter as u8, "Letter code incorrect");
+}
+
+#[test]
+fn args_csv_names() {
+    args_csv__char_a();
+    args_csv__char_b();
+    args_csv__char_z();
+}
+
+#[args(
+    csv = "tests/data/cases.csv";
+    char_c: 99, 'c';
+)]
+#[args(
+    offset_0: 0;
+    offset_1: 1 ! "code incorrect";
+)]
+#[test]
+fn args_csv_cartesic(code:_, letter:_, offset:_) {
+    assert_eq!(code + offset, letter as u8, "code incorrect");
+}
+
+#[args(
+    char_a: letter = 'a', code = 97;
+    char_b: code = 98, letter = 'b';
+)]
+#[test]
+fn args_named(code:_, letter:_) {
+    assert_eq!(code, letter as u8, "Letter code incorrect");
+}
+
+#[args(
+    char_a: letter = 'a', code = 97;
+    char_b: code = 98, letter = 'b';
+)]
+#[args(
+    offset_0: 0;
+    offset_1: 1 ! "code incorrect";
+)]
+#[test]
+fn args_named_cartesic(code:_, offset:_, letter:_) {
+    assert_eq!(code + offset, letter as u8, "code incorrect");
+}
+
+#[args(
+    summary = true;
+    char_a: 97, 'a';
+    char_b: 98, 'c' ! "code incorrect";
+)]
+#[args(
+    summary = true;
+    offset_0: 0;
+)]
+#[test]
+fn args_summary_cartesic(code:_, letter:_, offset:_) {
+    assert_eq!(code + offset, letter as u8, "code incorrect");
+}
+
+#[args(
+    zero: "0";
+    answer: "42";
+)]
+#[test]
+fn args_result(number:_) -> Result<(), std::num::ParseIntError> {
+    number.parse::<u32>()?;
+    Ok(())
+}
+
+#[args(
+    zero: "0";
+    answer: "42";
+)]
+#[args(
+    unit: ();
+)]
+#[test]
+fn args_result_cartesic(number:_, unit:_) -> Result<(), std::num::ParseIntError> {
+    number.parse::<u32>()?;
+    Ok(unit)
+}
+
 //-- #[test_args] ----------------------------------------------------------------------------------
 
 #[test_args]
@@ -35,3 +362,79 @@ fn test_noargs() {
 fn test_standard(code:_, letter:_) {
     assert_eq!(code, letter as u8, "Letter code incorrect");
 }
+
+#[test_args(
+    plain: 1;
+    ignored: #[ignore] 2;
+)]
+fn test_case_attributes(value:_) {
+    assert_eq!(1, value, "only the plain case should run");
+}
+
+#[test_args(
+    runtime = tokio::test;
+    char_a: 97, 'a';
+    char_b: 97, 'b' ! "code incorrect";
+)]
+async fn test_runtime(code:_, letter:_) {
+    let code = async { code }.await;
+    assert_eq!(code, letter as u8, "code incorrect");
+}
+
+#[test_args(
+    attr = core::prelude::v1::test;
+    char_a: 97, 'a';
+    char_b: 97, 'b' ! "code incorrect";
+)]
+fn test_attr(code:_, letter:_) {
+    assert_eq!(code, letter as u8, "code incorrect");
+}
+
+#[args(
+    char_a: 97, 'a';
+)]
+#[test_args(
+    runtime = tokio::test;
+    offset_0: 0;
+    offset_1: 1;
+)]
+async fn test_runtime_cartesic(code:_, letter:_, offset:_) {
+    assert!(async { code + offset }.await >= letter as u8);
+}
+
+//-- #[matrix] -------------------------------------------------------------------------------------
+
+#[matrix]
+#[test]
+fn matrix_noargs() {
+    assert!(true, "Letter code incorrect");
+}
+
+#[matrix(
+    code: a = 97, b = 98;
+    offset: zero = 0, one = 1;
+)]
+#[test]
+fn matrix_standard(code:_, offset:_) {
+    assert!((97..=99).contains(&(code + offset)));
+}
+
+#[test]
+fn matrix_standard_names() {
+    matrix_standard__code_a__offset_zero();
+    matrix_standard__code_a__offset_one();
+    matrix_standard__code_b__offset_zero();
+    matrix_standard__code_b__offset_one();
+}
+
+#[matrix(
+    letter: a = 'a', b = 'b';
+)]
+#[args(
+    code_97: 97;
+    code_99: 99 ! "code incorrect";
+)]
+#[test]
+fn matrix_args_cartesic(letter:_, code:_) {
+    assert!(letter as u8 >= code, "code incorrect");
+}
@@ -0,0 +1,13 @@
+use devbox_test_args::args;
+
+#[args(
+    char_a: 'a';
+    char_b: 'b';
+    char_a: 'c';
+)]
+#[test]
+fn parametrized_test_for(character:_) {
+    assert!(character.is_alphabetic());
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use devbox_test_args::args;
+
+#[args(
+    case_a: 1i64;
+)]
+#[test]
+fn parametrized_test_for<T: Into<i64>>(value: T) {
+    assert!(value.into() > 0);
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use devbox_test_args::args;
+
+#[args(
+    char_a: 97;
+)]
+#[test]
+fn parametrized_test_for(code:_, offset:_, letter:_) {
+    assert_eq!(code + offset, letter as u8);
+}
+
+fn main() {}
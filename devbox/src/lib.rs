@@ -63,12 +63,14 @@
 //!
 //! [build]: https://doc.rust-lang.org/devbox/build/index.html
 
+#![allow(clippy::test_attr_in_doctest)]
+
 //-- Re-exports ------------------------------------------------------------------------------------
 
 /// Small utility library for writing Rust tests.
 ///
 pub mod test {
-  pub use devbox_test_args::{args, test_args};
+  pub use devbox_test_args::{args, matrix, test_args};
 }
 
 /// Small utility library for writing Rust build sripts (build.rs).
@@ -138,7 +140,7 @@ pub mod test {
 ///     //-- Package webapp into server binary as Rust source code ------------------------
 ///
 ///     webrs.mk_from("Embed WebApp build into binary", &webwrk_dst, || {
-///         let mappings = webwrk_dst.files("**").into_iter().map(|file|
+///         let mappings = webwrk_dst.files("**").sorted().into_iter().map(|file|
 ///             format!(r#""{}" => Some(include_bytes!("{}")),"#,
 ///                 file.path().strip_prefix(&webwrk_dst.path()).unwrap().to_str().unwrap(),
 ///                 file.path().to_str().unwrap())